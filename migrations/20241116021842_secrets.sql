@@ -0,0 +1,41 @@
+-- 1. Create the secrets table used by the default secrets backend
+CREATE TABLE IF NOT EXISTS secrets (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+-- 2. Move realm client secrets out of the realms table
+INSERT OR REPLACE INTO secrets (key, value)
+SELECT 'realm/' || tenant_id || '/' || id || '/client_secret', client_secret
+FROM realms
+WHERE client_secret != '';
+
+UPDATE realms SET client_secret = '';
+
+-- 3. Move API keys and client secrets out of the settings JSON
+INSERT OR REPLACE INTO secrets (key, value)
+SELECT 'settings/' || key || '/api_key', json_extract(value, '$.api_key')
+FROM settings
+WHERE key IN ('okta_config', 'openai_config', 'anthropic_config')
+AND json_extract(value, '$.api_key') IS NOT NULL;
+
+UPDATE settings SET value = json_remove(value, '$.api_key')
+WHERE key IN ('okta_config', 'openai_config', 'anthropic_config');
+
+INSERT OR REPLACE INTO secrets (key, value)
+SELECT 'settings/' || key || '/client_secret', json_extract(value, '$.client_secret')
+FROM settings
+WHERE key IN ('onelogin_config', 'entra_config')
+AND json_extract(value, '$.client_secret') IS NOT NULL;
+
+UPDATE settings SET value = json_remove(value, '$.client_secret')
+WHERE key IN ('onelogin_config', 'entra_config');
+
+INSERT OR REPLACE INTO secrets (key, value)
+SELECT 'settings/' || key || '/service_account_key', json_extract(value, '$.service_account_key')
+FROM settings
+WHERE key = 'google_config'
+AND json_extract(value, '$.service_account_key') IS NOT NULL;
+
+UPDATE settings SET value = json_remove(value, '$.service_account_key')
+WHERE key = 'google_config';
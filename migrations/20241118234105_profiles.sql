@@ -0,0 +1,40 @@
+-- 1. Create the profiles table. A profile bundles a tenant/realm pair with
+--    its own migration source settings.
+CREATE TABLE IF NOT EXISTS profiles (
+    name TEXT PRIMARY KEY,
+    tenant_id TEXT,
+    realm_id TEXT
+);
+
+-- 2. Carry the current default tenant/realm over into the default profile
+INSERT INTO profiles (name, tenant_id, realm_id)
+SELECT 'default', tenant_id, realm_id
+FROM defaults
+WHERE id = 1;
+
+INSERT OR IGNORE INTO profiles (name) VALUES ('default');
+
+DROP TABLE IF EXISTS defaults;
+
+-- 3. Scope settings per profile. Settings shared by all profiles are stored
+--    under the empty profile name.
+CREATE TABLE settings_new (
+    profile TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (profile, key)
+);
+
+INSERT INTO settings_new (profile, key, value)
+SELECT
+    CASE
+        WHEN key IN ('secrets_backend', 'openai_config', 'anthropic_config', 'default_ai_provider') THEN ''
+        ELSE 'default'
+    END,
+    key,
+    value
+FROM settings;
+
+DROP TABLE settings;
+
+ALTER TABLE settings_new RENAME TO settings;
@@ -0,0 +1,16 @@
+-- Journal of every mutating request sent to the Beyond Identity API. Deletes
+-- keep the resource as it was just before it was deleted.
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER NOT NULL,
+    os_user TEXT NOT NULL,
+    command_line TEXT NOT NULL,
+    method TEXT NOT NULL,
+    url TEXT NOT NULL,
+    body_hash TEXT,
+    status INTEGER,
+    resource_id TEXT,
+    pre_delete_body TEXT
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_timestamp ON audit_log (timestamp);
@@ -0,0 +1,12 @@
+-- 1. Keep the state before patches as well as deletes, and the request body,
+--    so recorded actions can be undone.
+ALTER TABLE audit_log RENAME COLUMN pre_delete_body TO pre_image;
+ALTER TABLE audit_log ADD COLUMN request_body TEXT;
+ALTER TABLE audit_log ADD COLUMN undone_at INTEGER;
+
+-- 2. IDs of resources recreated by undo, so later actions referring to the
+--    original ID can be mapped onto the new one.
+CREATE TABLE IF NOT EXISTS recreated_ids (
+    old_id TEXT PRIMARY KEY,
+    new_id TEXT NOT NULL
+);
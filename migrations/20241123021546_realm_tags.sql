@@ -0,0 +1,8 @@
+-- Local tags on configured realms, e.g. "protected" to guard a realm against
+-- bulk deletes.
+CREATE TABLE IF NOT EXISTS realm_tags (
+    tenant_id TEXT NOT NULL,
+    realm_id TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (tenant_id, realm_id, tag)
+);
@@ -0,0 +1,16 @@
+-- Identities offboarded with `helper offboard`, with a receipt of every step
+-- so the offboarding can be reversed, and the end of the grace period after
+-- which the identity is deleted.
+CREATE TABLE IF NOT EXISTS offboardings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    tenant_id TEXT NOT NULL,
+    realm_id TEXT NOT NULL,
+    identity_id TEXT NOT NULL,
+    offboarded_at INTEGER NOT NULL,
+    receipt TEXT NOT NULL,
+    delete_after INTEGER,
+    deleted_at INTEGER,
+    reinstated_at INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS offboardings_identity ON offboardings (tenant_id, realm_id, identity_id);
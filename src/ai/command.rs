@@ -3,13 +3,13 @@ use std::env;
 use crate::beyond_identity::api::common::api_client::ApiClient;
 use crate::common::command::ambassador_impl_Executable;
 use crate::common::database::models::AiProvider;
+use crate::common::http;
 use crate::common::{command::Executable, error::BiError};
 use crate::Cli;
 
 use async_trait::async_trait;
 use clap::{Args, Subcommand};
 use clap_markdown::MarkdownOptions;
-use reqwest::Client;
 use serde_json::json;
 
 // ====================================
@@ -119,7 +119,7 @@ async fn openai(api_client: &ApiClient, prompt: &str) -> Result<String, BiError>
         }
     };
 
-    let response = Client::new()
+    let response = http::client()
         .post("https://api.openai.com/v1/chat/completions")
         .header("Content-Type", "application/json")
         .header("Authorization", format!("Bearer {}", api_key))
@@ -158,7 +158,7 @@ pub async fn anthropic(api_client: &ApiClient, prompt: &str) -> Result<String, B
         }
     };
 
-    let response = Client::new()
+    let response = http::client()
         .post("https://api.anthropic.com/v1/messages")
         .header("Content-Type", "application/json")
         .header("x-api-key", api_key)
@@ -0,0 +1,203 @@
+use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::beyond_identity::api::common::middleware::authorization::token;
+use crate::beyond_identity::api::common::middleware::rate_limit::RespectRateLimitMiddleware;
+use crate::common::command::ambassador_impl_Executable;
+use crate::common::database::models::Token;
+use crate::common::{command::Executable, error::BiError};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use clap::{Args, Subcommand};
+use serde_json::Value;
+
+// ====================================
+// Auth Commands
+// ====================================
+
+/// Commands for inspecting and managing the access tokens the CLI uses to call the Beyond Identity API.
+#[derive(Subcommand, ambassador::Delegate)]
+#[delegate(Executable)]
+pub enum AuthCommands {
+    /// Print a bearer token for the default tenant/realm, fetching a new one if the cached token expired.
+    Token(PrintToken),
+
+    /// Display cached tokens with their expiry, scopes and claims.
+    Status(Status),
+
+    /// Remove the cached token of the default tenant/realm.
+    Logout(Logout),
+}
+
+// Decode the header and claims of a JWT without verifying its signature
+fn decode_jwt(token: &str) -> Result<(Value, Value), BiError> {
+    let mut parts = token.split('.');
+    let mut decode_part = || -> Result<Value, BiError> {
+        let part = parts
+            .next()
+            .ok_or(BiError::StringError("Malformed JWT".to_string()))?;
+        let bytes = URL_SAFE_NO_PAD
+            .decode(part.trim_end_matches('='))
+            .map_err(|e| BiError::StringError(format!("Malformed JWT: {}", e)))?;
+        Ok(serde_json::from_slice(&bytes)?)
+    };
+    let header = decode_part()?;
+    let claims = decode_part()?;
+    Ok((header, claims))
+}
+
+// Scopes granted by a token, from either the `scope` or the `scp` claim
+fn scopes(claims: &Value) -> String {
+    match claims.get("scope").or_else(|| claims.get("scp")) {
+        Some(Value::String(scope)) => scope.clone(),
+        Some(Value::Array(scopes)) => scopes
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => "".to_string(),
+    }
+}
+
+// ====================================
+// Auth Token
+// ====================================
+
+#[derive(Args)]
+pub struct PrintToken {
+    /// Print the decoded header and claims instead of the raw token
+    #[clap(long)]
+    decode: bool,
+}
+
+#[async_trait]
+impl Executable for PrintToken {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        let access_token = token(
+            &api_client.db,
+            &RespectRateLimitMiddleware::new_client(),
+            &None,
+            &None,
+        )
+        .await?;
+
+        if self.decode {
+            let (header, claims) = decode_jwt(&access_token)?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "header": header,
+                    "claims": claims,
+                }))?
+            );
+        } else {
+            println!("{}", access_token);
+        }
+        Ok(())
+    }
+}
+
+// ====================================
+// Auth Status
+// ====================================
+
+#[derive(Args)]
+pub struct Status;
+
+#[async_trait]
+impl Executable for Status {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        let tokens = api_client.db.get_all_tokens().await?;
+        if tokens.is_empty() {
+            println!("No cached tokens.");
+            return Ok(());
+        }
+
+        let default = api_client.db.get_default_tenant_and_realm().await?;
+        let now = Utc::now().timestamp();
+        for (i, t) in tokens.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            print_token_status(
+                t,
+                now,
+                default.as_ref().is_some_and(|(tenant, realm)| {
+                    tenant.id == t.tenant_id && realm.id == t.realm_id
+                }),
+            );
+        }
+        Ok(())
+    }
+}
+
+fn print_token_status(token: &Token, now: i64, is_default: bool) {
+    let expires_at = DateTime::<Utc>::from_timestamp(token.expires_at, 0)
+        .map(|d| d.to_rfc3339())
+        .unwrap_or_else(|| token.expires_at.to_string());
+    let remaining = token.expires_at - now;
+    let validity = if remaining > 0 {
+        format!("valid for {}m {}s", remaining / 60, remaining % 60)
+    } else {
+        "expired".to_string()
+    };
+
+    println!(
+        "Tenant: {}  Realm: {}  Application: {}{}",
+        token.tenant_id,
+        token.realm_id,
+        token.application_id,
+        if is_default { "  (default)" } else { "" }
+    );
+    println!("Expires at: {} ({})", expires_at, validity);
+    match decode_jwt(&token.access_token) {
+        Ok((_, claims)) => {
+            println!("Scopes: {}", scopes(&claims));
+            println!(
+                "Claims: {}",
+                serde_json::to_string_pretty(&claims).unwrap_or_default()
+            );
+        }
+        Err(e) => println!("Claims: unavailable ({})", e),
+    }
+}
+
+// ====================================
+// Auth Logout
+// ====================================
+
+#[derive(Args)]
+pub struct Logout {
+    /// Remove the cached tokens of every tenant/realm
+    #[clap(long)]
+    all: bool,
+}
+
+#[async_trait]
+impl Executable for Logout {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        let db = &api_client.db;
+
+        if self.all {
+            let removed = db.delete_all_tokens().await?;
+            println!("Removed {} cached token(s)", removed);
+            return Ok(());
+        }
+
+        let (tenant, realm) =
+            db.get_default_tenant_and_realm()
+                .await?
+                .ok_or(BiError::StringError(
+                    "No default tenant/realm set".to_string(),
+                ))?;
+        db.delete_token(&tenant.id, &realm.id).await?;
+        println!(
+            "Removed cached token for tenant {} realm {}",
+            tenant.id, realm.id
+        );
+        Ok(())
+    }
+}
@@ -1,15 +1,18 @@
 use std::usize;
 
+use super::middleware::audit::AuditMiddleware;
 use super::middleware::authorization::AuthorizationMiddleware;
+use super::middleware::dry_run::DryRunMiddleware;
 use super::middleware::logging::LoggingMiddleware;
 use super::middleware::rate_limit::RespectRateLimitMiddleware;
+use super::middleware::record_replay::RecordReplayMiddleware;
+use super::middleware::retry::RetryMiddleware;
 
 use crate::common::database::models::Realm;
 use crate::common::database::models::Tenant;
 use crate::common::{database::Database, error::BiError};
 
 use http::Method;
-use reqwest::Client;
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use serde::de::{self, Deserializer, MapAccess};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
@@ -27,10 +30,12 @@ impl ApiClient {
     pub async fn new(tenant: Option<Tenant>, realm: Option<Realm>) -> Self {
         let db = Database::initialize().await.unwrap();
 
-        let http_client = Client::new();
+        let http_client = crate::common::http::client();
 
         let rate_limit_middleware = ClientBuilder::new(http_client.clone())
             .with(RespectRateLimitMiddleware)
+            .with(RetryMiddleware)
+            .with(RecordReplayMiddleware)
             .build();
 
         let auth_middleware = AuthorizationMiddleware::new(
@@ -41,9 +46,13 @@ impl ApiClient {
         );
 
         let client = ClientBuilder::new(http_client)
+            .with(AuditMiddleware::new(db.clone()))
             .with(auth_middleware)
+            .with(DryRunMiddleware)
             .with(LoggingMiddleware)
             .with(RespectRateLimitMiddleware)
+            .with(RetryMiddleware)
+            .with(RecordReplayMiddleware)
             .build();
 
         Self {
@@ -314,6 +323,27 @@ impl URLBuilder {
         self
     }
 
+    /// Adds the segments of a `/`-separated path to the URL, replacing
+    /// `{tenant}` and `{realm}` segments with the configured IDs.
+    pub fn add_template_path(mut self, path: &str) -> Self {
+        let tenant_id = self.tenant_id.clone();
+        let realm_id = self.realm_id.clone();
+        self.url_mut()
+            .path_segments_mut()
+            .expect("Cannot be base")
+            .pop_if_empty()
+            .extend(
+                path.split('/').filter(|segment| !segment.is_empty()).map(
+                    |segment| match segment {
+                        "{tenant}" => tenant_id.as_str(),
+                        "{realm}" => realm_id.as_str(),
+                        _ => segment,
+                    },
+                ),
+            );
+        self
+    }
+
     /// Appends a colon suffix to the last path segment.
     pub fn add_custom_method(mut self, suffix: &str) -> Self {
         // Get the current path segments as a vector of strings.
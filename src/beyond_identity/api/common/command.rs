@@ -4,6 +4,7 @@ use crate::beyond_identity::api::credentials::command::CredentialCommands;
 use crate::beyond_identity::api::groups::command::GroupCommands;
 use crate::beyond_identity::api::identities::command::IdentityCommands;
 use crate::beyond_identity::api::realms::command::RealmCommands;
+use crate::beyond_identity::api::request::command::Request;
 use crate::beyond_identity::api::tenants::command::TenantCommands;
 use crate::common::command::ambassador_impl_Executable;
 use crate::common::command::Executable;
@@ -42,4 +43,7 @@ pub enum BeyondIdentityApiCommands {
     /// Authenticator Configs
     #[clap(subcommand)]
     AuthenticatorConfigs(AuthenticatorConfigCommands),
+
+    /// Send a raw request to any Beyond Identity API endpoint
+    Request(Request),
 }
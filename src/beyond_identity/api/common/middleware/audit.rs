@@ -0,0 +1,180 @@
+use crate::common::database::models::AuditEntry;
+use crate::common::database::Database;
+
+use super::dry_run::DryRunMiddleware;
+use super::record_replay::redact_value;
+use super::record_replay::RecordReplayMiddleware;
+
+use http::Extensions;
+use log::warn;
+use reqwest::{Method, Request, Response};
+use reqwest_middleware::{Error, Middleware, Next, Result};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+const REDACTED: &str = "[REDACTED]";
+
+// Command line flags whose values are not written to the journal
+const SENSITIVE_ARG_WORDS: &[&str] = &["secret", "token", "password", "key"];
+
+/// Records every mutating request in the local audit journal. Deletes and
+/// patches are preceded by a GET of the resource so its prior state is kept,
+/// with secrets redacted as in request bodies. Failing to write the journal is
+/// logged but does not fail the request. Nothing is recorded during dry runs,
+/// as nothing is sent.
+pub struct AuditMiddleware {
+    db: Database,
+}
+
+impl AuditMiddleware {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for AuditMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        if !is_mutating(req.method())
+            || DryRunMiddleware::enabled()
+            || RecordReplayMiddleware::replaying()
+        {
+            return next.run(req, extensions).await;
+        }
+
+        let method = req.method().clone();
+        let url = req.url().clone();
+        let body = req
+            .body()
+            .and_then(|b| b.as_bytes())
+            .filter(|b| !b.is_empty());
+        let body_hash = body.map(|b| hex(&Sha256::digest(b)));
+        let request_body = body
+            .and_then(|b| serde_json::from_slice::<Value>(b).ok())
+            .map(|mut value| {
+                redact_value(&mut value);
+                value.to_string()
+            });
+
+        let pre_image = if method == Method::DELETE || method == Method::PATCH {
+            let get = Request::new(Method::GET, url.clone());
+            match next.clone().run(get, extensions).await {
+                // Secrets are redacted like in the request body. Pre-images that
+                // aren't JSON can't be redacted and are not kept.
+                Ok(response) if response.status().is_success() => response
+                    .bytes()
+                    .await
+                    .ok()
+                    .and_then(|b| serde_json::from_slice::<Value>(&b).ok())
+                    .map(|mut value| {
+                        redact_value(&mut value);
+                        value.to_string()
+                    }),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        let (status, resource_id, result) = match next.run(req, extensions).await {
+            Ok(response) => {
+                let status = response.status();
+                let headers = response.headers().clone();
+                let version = response.version();
+                let bytes = response.bytes().await?;
+
+                let mut rebuilt = http::Response::builder().status(status).version(version);
+                for (name, value) in headers.iter() {
+                    rebuilt = rebuilt.header(name, value);
+                }
+                let resource_id = resource_id(&bytes)
+                    .or_else(|| pre_image.as_deref().and_then(|b| resource_id(b.as_bytes())));
+                let response = rebuilt
+                    .body(bytes)
+                    .map(Response::from)
+                    .map_err(|e| Error::Middleware(anyhow::anyhow!(e.to_string())));
+                (Some(status.as_u16() as i64), resource_id, response)
+            }
+            Err(e) => (None, None, Err(e)),
+        };
+
+        let entry = AuditEntry {
+            id: 0,
+            timestamp: chrono::Utc::now().timestamp(),
+            os_user: os_user(),
+            command_line: command_line(),
+            method: method.to_string(),
+            url: url.to_string(),
+            body_hash,
+            status,
+            resource_id,
+            pre_image,
+            request_body,
+            undone_at: None,
+        };
+        if let Err(e) = self.db.add_audit_entry(&entry).await {
+            warn!(
+                "Failed to record {} {} in the audit journal: {}",
+                method, url, e
+            );
+        }
+
+        result
+    }
+}
+
+fn is_mutating(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    )
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// ID of the resource in a response, either at the top level or inside an
+// envelope such as {"credential_binding_job": {...}}
+fn resource_id(body: &[u8]) -> Option<String> {
+    let value: Value = serde_json::from_slice(body).ok()?;
+    let id = |v: &Value| v.get("id").and_then(Value::as_str).map(str::to_string);
+    id(&value).or_else(|| value.as_object()?.values().find_map(id))
+}
+
+fn os_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+// The invocation's arguments, with the values of flags such as --client-secret redacted
+fn command_line() -> String {
+    let is_sensitive = |flag: &str| {
+        let flag = flag.to_lowercase();
+        SENSITIVE_ARG_WORDS.iter().any(|word| flag.contains(word))
+    };
+
+    let mut redact_next = false;
+    std::env::args()
+        .map(|arg| {
+            if std::mem::take(&mut redact_next) {
+                return REDACTED.to_string();
+            }
+            match arg.strip_prefix("--").map(|flag| flag.split_once('=')) {
+                Some(Some((flag, _))) if is_sensitive(flag) => format!("--{}={}", flag, REDACTED),
+                Some(None) if is_sensitive(&arg) => {
+                    redact_next = true;
+                    arg
+                }
+                _ => arg,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -1,4 +1,5 @@
 use crate::beyond_identity::api::common::api_client::URLBuilder;
+use crate::beyond_identity::api::common::middleware::record_replay::RecordReplayMiddleware;
 use crate::common::database::models::Realm;
 use crate::common::database::models::Tenant;
 use crate::common::database::models::Token;
@@ -13,7 +14,9 @@ use reqwest_middleware::{
     ClientWithMiddleware, Error, Middleware, Next, Result as MiddlewareResult,
 };
 use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
 use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
 
 pub struct AuthorizationMiddleware {
     db: Database,
@@ -64,25 +67,22 @@ impl Middleware for AuthorizationMiddleware {
 
         let mut response = next.clone().run(req, extensions).await?;
 
-        if response.status() == StatusCode::FORBIDDEN {
-            log::debug!("Received 403 Forbidden, attempting to refresh token and retry request.");
-
-            // Invalidate the current token
-            if let (Some(tenant), Some(realm)) = (&self.tenant, &self.realm) {
-                self.db
-                    .delete_token(&tenant.id, &realm.id)
-                    .await
-                    .map_err(|e| {
-                        reqwest_middleware::Error::Middleware(
-                            BiError::StringError(e.to_string()).into(),
-                        )
-                    })?;
-            }
+        if is_rejected(response.status()) {
+            log::debug!(
+                "Received {}, attempting to refresh token and retry request.",
+                response.status()
+            );
 
-            // Fetch a new token
-            let new_token = token(&self.db, &self.client, &self.tenant, &self.realm)
-                .await
-                .map_err(|e| reqwest_middleware::Error::Middleware(e.into()))?;
+            // Invalidate the rejected token and fetch a new one
+            let new_token = refresh_token(
+                &self.db,
+                &self.client,
+                &self.tenant,
+                &self.realm,
+                &fetched_token,
+            )
+            .await
+            .map_err(|e| reqwest_middleware::Error::Middleware(e.into()))?;
 
             req_for_retry.headers_mut().insert(
                 reqwest::header::AUTHORIZATION,
@@ -91,9 +91,10 @@ impl Middleware for AuthorizationMiddleware {
 
             response = next.run(req_for_retry, extensions).await?;
 
-            if response.status() == StatusCode::FORBIDDEN {
+            if is_rejected(response.status()) {
                 log::error!(
-                    "Received 403 Forbidden after refreshing the token. This may indicate invalid credentials, insufficient permissions, or a server-side issue. Check the token, request headers, and server configuration."
+                    "Received {} after refreshing the token. This may indicate invalid credentials, insufficient permissions, or a server-side issue. Check the token, request headers, and server configuration.",
+                    response.status()
                 );
             }
         }
@@ -102,33 +103,83 @@ impl Middleware for AuthorizationMiddleware {
     }
 }
 
+// Responses that indicate the bearer token was not accepted
+fn is_rejected(status: StatusCode) -> bool {
+    status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct ApiTokenResponse {
     access_token: String,
     expires_in: u64,
 }
 
-async fn token(
+/// Default number of seconds before expiry a cached token is refreshed.
+pub const DEFAULT_REFRESH_MARGIN_SECS: u64 = 60;
+
+// Refresh margin set from the command line for this process
+static REFRESH_MARGIN_SECS: OnceLock<u64> = OnceLock::new();
+
+// Serializes token fetches so concurrent requests that all find the cached
+// token stale only fetch a single new one.
+static REFRESH_LOCK: Mutex<()> = Mutex::const_new(());
+
+// Tokens fetched while replaying. Recorded tokens are redacted, so they are
+// only kept in memory and never replace a real cached token.
+static REPLAYED_TOKENS: std::sync::Mutex<Vec<Token>> = std::sync::Mutex::new(Vec::new());
+
+// Set how many seconds before expiry cached tokens are refreshed in this process
+pub fn configure_refresh_margin(secs: u64) {
+    _ = REFRESH_MARGIN_SECS.set(secs);
+}
+
+fn refresh_margin() -> u64 {
+    REFRESH_MARGIN_SECS
+        .get()
+        .copied()
+        .unwrap_or(DEFAULT_REFRESH_MARGIN_SECS)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+// Get tenant and realm, using defaults if not provided
+async fn resolve_tenant_and_realm(
     db: &Database,
-    client: &Client,
     tenant: &Option<Tenant>,
     realm: &Option<Realm>,
-) -> Result<String, BiError> {
-    // Get tenant and realm, using defaults if not provided
-    let (tenant, realm) = match (tenant, realm) {
-        (Some(t), Some(r)) => (t.clone(), r.clone()),
+) -> Result<(Tenant, Realm), BiError> {
+    match (tenant, realm) {
+        (Some(t), Some(r)) => Ok((t.clone(), r.clone())),
         _ => db
             .get_default_tenant_and_realm()
             .await?
-            .map(|(t, r)| (t, r))
-            .ok_or_else(|| BiError::StringError("No default tenant/realm set".to_string()))?,
-    };
+            .ok_or_else(|| BiError::StringError("No default tenant/realm set".to_string())),
+    }
+}
 
-    if let Some(token) = db.get_token(&tenant.id, &realm.id).await? {
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+// Get the cached token if it is not within the refresh margin of its expiry
+async fn cached_token(
+    db: &Database,
+    tenant: &Tenant,
+    realm: &Realm,
+) -> Result<Option<String>, BiError> {
+    let stored = if RecordReplayMiddleware::replaying() {
+        REPLAYED_TOKENS
+            .lock()
+            .map_err(|e| BiError::StringError(e.to_string()))?
+            .iter()
+            .find(|t| t.tenant_id == tenant.id && t.realm_id == realm.id)
+            .cloned()
+    } else {
+        db.get_token(&tenant.id, &realm.id).await?
+    };
+    if let Some(token) = stored {
+        let current_time = now();
 
         log::debug!(
             "Current time: {}, stored token expires at: {}",
@@ -136,15 +187,75 @@ async fn token(
             token.expires_at
         );
 
-        if token.expires_at >= 0 && (token.expires_at as u64) > current_time {
+        if token.expires_at >= 0
+            && (token.expires_at as u64) > current_time.saturating_add(refresh_margin())
+        {
             log::debug!("Using stored bearer token for all requests");
-            return Ok(token.access_token);
+            return Ok(Some(token.access_token));
         }
     }
+    Ok(None)
+}
+
+pub async fn token(
+    db: &Database,
+    client: &Client,
+    tenant: &Option<Tenant>,
+    realm: &Option<Realm>,
+) -> Result<String, BiError> {
+    let (tenant, realm) = resolve_tenant_and_realm(db, tenant, realm).await?;
+
+    if let Some(token) = cached_token(db, &tenant, &realm).await? {
+        return Ok(token);
+    }
+
+    let _guard = REFRESH_LOCK.lock().await;
+
+    // Another request may have fetched a token while we waited for the lock
+    if let Some(token) = cached_token(db, &tenant, &realm).await? {
+        return Ok(token);
+    }
 
     log::debug!("No valid token found. Fetching a new one.");
+    fetch_token(db, client, tenant, realm).await
+}
+
+// Replace a token the API rejected. If a concurrent request already replaced
+// it, the new cached token is used instead of fetching yet another one.
+async fn refresh_token(
+    db: &Database,
+    client: &Client,
+    tenant: &Option<Tenant>,
+    realm: &Option<Realm>,
+    rejected_token: &str,
+) -> Result<String, BiError> {
+    let (tenant, realm) = resolve_tenant_and_realm(db, tenant, realm).await?;
+
+    let _guard = REFRESH_LOCK.lock().await;
+
+    if let Some(token) = cached_token(db, &tenant, &realm).await? {
+        if token != rejected_token {
+            return Ok(token);
+        }
+    }
+
+    if RecordReplayMiddleware::replaying() {
+        REPLAYED_TOKENS
+            .lock()
+            .map_err(|e| BiError::StringError(e.to_string()))?
+            .retain(|t| t.tenant_id != tenant.id || t.realm_id != realm.id);
+    } else {
+        db.delete_token(&tenant.id, &realm.id).await?;
+    }
+    fetch_token(db, client, tenant, realm).await
+}
 
-    // If no valid token, fetch a new one
+async fn fetch_token(
+    db: &Database,
+    client: &Client,
+    tenant: Tenant,
+    realm: Realm,
+) -> Result<String, BiError> {
     let url = URLBuilder::build(tenant.clone(), realm.clone())
         .auth()
         .add_tenant()
@@ -177,11 +288,7 @@ async fn token(
     let token_response: ApiTokenResponse = serde_json::from_str(&response_text)?;
 
     // Calculate the expiration time
-    let current_time = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    let expires_at = current_time + token_response.expires_in;
+    let expires_at = now() + token_response.expires_in;
 
     log::debug!(
         "Token expires in: {} seconds, setting expires_at to: {}",
@@ -197,7 +304,14 @@ async fn token(
         application_id: realm.application_id,
     };
 
-    db.set_token(token.clone()).await?;
+    if RecordReplayMiddleware::replaying() {
+        REPLAYED_TOKENS
+            .lock()
+            .map_err(|e| BiError::StringError(e.to_string()))?
+            .push(token.clone());
+    } else {
+        db.set_token(token.clone()).await?;
+    }
 
     Ok(token.access_token)
 }
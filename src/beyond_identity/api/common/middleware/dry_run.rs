@@ -0,0 +1,274 @@
+use http::Extensions;
+use reqwest::{Method, Request, Response, Url};
+use reqwest_middleware::{Error, Middleware, Next, Result};
+use serde_json::{json, Map, Value};
+use std::sync::OnceLock;
+
+// Whether --dry-run was passed for this process
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+// ID given to resources that a dry run pretends to create
+const DRY_RUN_ID: &str = "dry-run";
+
+/// Prints every mutating request instead of sending it. Requests still pass
+/// through URL building, serialization and authorization, so what is printed
+/// is exactly what would be sent. Callers receive a made-up successful
+/// response: the current resource with the patch applied for PATCH, the
+/// request body with a placeholder ID for creates, and `{}` for deletes.
+pub struct DryRunMiddleware;
+
+impl DryRunMiddleware {
+    // Enable dry runs for every client created in this process
+    pub fn configure(enabled: bool) {
+        _ = ENABLED.set(enabled);
+    }
+
+    pub fn enabled() -> bool {
+        ENABLED.get().copied().unwrap_or(false)
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for DryRunMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        if !Self::enabled() || matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS)
+        {
+            return next.run(req, extensions).await;
+        }
+
+        let body: Option<Value> = req
+            .body()
+            .and_then(|b| b.as_bytes())
+            .filter(|b| !b.is_empty())
+            .map(|b| {
+                serde_json::from_slice(b)
+                    .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(b).to_string()))
+            });
+
+        eprintln!("Dry run: {} {}", req.method(), req.url());
+        if let Some(body) = &body {
+            eprintln!(
+                "{}",
+                serde_json::to_string_pretty(body).map_err(middleware_error)?
+            );
+        }
+
+        let response = match *req.method() {
+            Method::DELETE => json!({}),
+            Method::PATCH => {
+                let mut current = current_state(&req, req.url().clone(), extensions, next).await;
+                merge(&mut current, unwrap_envelope(body.unwrap_or_default()));
+                current
+            }
+            _ => match custom_method_resource(req.url()) {
+                // e.g. groups/{id}:addMembers answers with the group
+                Some(url) => current_state(&req, url, extensions, next).await,
+                None => created(req.url(), body.unwrap_or_default()),
+            },
+        };
+
+        let response = http::Response::builder()
+            .status(200)
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(response.to_string())
+            .map_err(middleware_error)?;
+        Ok(Response::from(response))
+    }
+}
+
+// The resource as it currently is, fetched with the original request's headers
+// so authorization carries over. Empty if it can't be fetched.
+async fn current_state(
+    req: &Request,
+    url: Url,
+    extensions: &mut Extensions,
+    next: Next<'_>,
+) -> Value {
+    let mut get = Request::new(Method::GET, url);
+    *get.headers_mut() = req.headers().clone();
+    get.headers_mut().remove(http::header::CONTENT_TYPE);
+    match next.run(get, extensions).await {
+        Ok(response) if response.status().is_success() => response
+            .json()
+            .await
+            .unwrap_or_else(|_| Value::Object(Map::new())),
+        _ => Value::Object(Map::new()),
+    }
+}
+
+// URL of the resource a custom method such as `:revoke` acts on
+fn custom_method_resource(url: &Url) -> Option<Url> {
+    let (parent, last) = url.path().rsplit_once('/')?;
+    let (resource, _) = last.split_once(':')?;
+    let path = format!("{}/{}", parent, resource);
+    let mut url = url.clone();
+    url.set_path(&path);
+    url.set_query(None);
+    Some(url)
+}
+
+// A create whose response isn't just the created resource
+struct CreateResponse {
+    // Collection the resource is created in
+    collection: &'static str,
+    // Key the created resource is returned under
+    envelope: &'static str,
+    // Other fields of the response, null in a dry run
+    extra_fields: &'static [&'static str],
+    // Timestamps of the resource besides create_time and update_time
+    timestamps: &'static [&'static str],
+}
+
+const CREATE_RESPONSES: &[CreateResponse] = &[CreateResponse {
+    collection: "credential-binding-jobs",
+    envelope: "credential_binding_job",
+    extra_fields: &["credential_binding_link"],
+    timestamps: &["expire_time"],
+}];
+
+// A plausible response to a create: the request body with the IDs and
+// timestamps the server would have filled in, in the shape the server
+// answers with
+fn created(url: &Url, body: Value) -> Value {
+    let segments: Vec<&str> = url.path_segments().map(|s| s.collect()).unwrap_or_default();
+    let collection = segments.last().copied().unwrap_or_default();
+    let response = CREATE_RESPONSES.iter().find(|r| r.collection == collection);
+
+    let mut value = unwrap_envelope(body);
+    if let Value::Object(fields) = &mut value {
+        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        fields.insert("id".to_string(), json!(DRY_RUN_ID));
+        // IDs of the parents in the path, e.g. identity_id for identities/{id}/...
+        let start = segments.iter().position(|s| *s == "tenants").unwrap_or(0);
+        let parents = segments[start..].split_last().map_or(&[][..], |(_, p)| p);
+        for pair in parents.chunks(2) {
+            if let [parent, id] = pair {
+                fields
+                    .entry(format!("{}_id", singular(parent)))
+                    .or_insert(json!(id));
+            }
+        }
+        let timestamps = response.map(|r| r.timestamps).unwrap_or_default();
+        for field in ["create_time", "update_time"].iter().chain(timestamps) {
+            fields.entry(*field).or_insert(json!(now));
+        }
+    }
+
+    match response {
+        Some(response) => {
+            let mut envelope = Map::new();
+            envelope.insert(response.envelope.to_string(), value);
+            for field in response.extra_fields {
+                envelope.insert(field.to_string(), Value::Null);
+            }
+            Value::Object(envelope)
+        }
+        None => value,
+    }
+}
+
+// Field name stem of a collection, e.g. `identity` for `identities` and
+// `resource_server` for `resource-servers`
+fn singular(collection: &str) -> String {
+    let collection = collection.replace('-', "_");
+    match collection.strip_suffix("ies") {
+        Some(stem) => format!("{}y", stem),
+        None => collection.trim_end_matches('s').to_string(),
+    }
+}
+
+// Request bodies wrap the resource in an envelope such as {"identity": {...}}
+fn unwrap_envelope(body: Value) -> Value {
+    match body {
+        Value::Object(fields) if fields.len() == 1 => match fields.into_iter().next() {
+            Some((_, inner @ Value::Object(_))) => inner,
+            Some((key, other)) => json!({ key: other }),
+            None => Value::Object(Map::new()),
+        },
+        other => other,
+    }
+}
+
+fn merge(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+fn middleware_error(e: impl ToString) -> Error {
+    Error::Middleware(anyhow::anyhow!(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beyond_identity::api::credential_binding_jobs::types::CredentialBindingJobEnvelope;
+    use crate::beyond_identity::api::identities::types::Identity;
+
+    #[test]
+    fn unwraps_single_envelopes() {
+        assert_eq!(
+            unwrap_envelope(json!({ "group": { "display_name": "Admins" } })),
+            json!({ "display_name": "Admins" })
+        );
+        assert_eq!(
+            unwrap_envelope(json!({ "identity_ids": ["a"] })),
+            json!({ "identity_ids": ["a"] })
+        );
+        assert_eq!(
+            unwrap_envelope(json!({ "a": {}, "b": {} })),
+            json!({ "a": {}, "b": {} })
+        );
+    }
+
+    #[test]
+    fn created_resource_has_path_ids_and_timestamps() {
+        let url =
+            Url::parse("https://api-us.beyondidentity.com/v1/tenants/t1/realms/r1/identities")
+                .unwrap();
+        let response = created(
+            &url,
+            json!({ "identity": {
+                "display_name": "Jane",
+                "traits": { "type": "traits_v0", "username": "jane" },
+            }}),
+        );
+        let identity: Identity = serde_json::from_value(response).unwrap();
+        assert_eq!(identity.id, DRY_RUN_ID);
+        assert_eq!(identity.tenant_id, "t1");
+        assert_eq!(identity.realm_id, "r1");
+        assert_eq!(identity.display_name, "Jane");
+    }
+
+    #[test]
+    fn created_binding_job_keeps_the_response_envelope() {
+        let url = Url::parse(
+            "https://api-us.beyondidentity.com/v1/tenants/t1/realms/r1/identities/i1/credential-binding-jobs",
+        )
+        .unwrap();
+        let response = created(
+            &url,
+            json!({ "job": {
+                "delivery_method": "EMAIL",
+                "authenticator_config_id": "a1",
+            }}),
+        );
+        let envelope: CredentialBindingJobEnvelope = serde_json::from_value(response).unwrap();
+        let job = envelope.credential_binding_job;
+        assert_eq!(job.id, DRY_RUN_ID);
+        assert_eq!(job.identity_id, "i1");
+        assert_eq!(job.realm_id, "r1");
+        assert!(!job.expire_time.is_empty());
+        assert!(envelope.credential_binding_link.is_none());
+    }
+}
@@ -1,3 +1,7 @@
+pub mod audit;
 pub mod authorization;
+pub mod dry_run;
 pub mod logging;
 pub mod rate_limit;
+pub mod record_replay;
+pub mod retry;
@@ -1,13 +1,17 @@
+use super::retry::{retry_after, RetryMiddleware};
+
 use log::debug;
-use reqwest::Client;
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, Error, Middleware, Next};
 
 pub struct RespectRateLimitMiddleware;
 
 impl RespectRateLimitMiddleware {
     pub fn new_client() -> ClientWithMiddleware {
-        let client = Client::new();
-        ClientBuilder::new(client).with(Self).build()
+        let client = crate::common::http::client();
+        ClientBuilder::new(client)
+            .with(Self)
+            .with(RetryMiddleware)
+            .build()
     }
 }
 
@@ -34,17 +38,9 @@ impl Middleware for RespectRateLimitMiddleware {
 
             if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
                 debug!("Received TOO_MANY_REQUESTS status code.");
-                if let Some(delay_secs) = response
-                    .headers()
-                    .get(reqwest::header::RETRY_AFTER)
-                    .and_then(|header| header.to_str().ok())
-                    .and_then(|s| s.parse().ok())
-                {
-                    debug!(
-                        "Received RETRY_AFTER header. Retrying after {} seconds",
-                        delay_secs
-                    );
-                    tokio::time::sleep(std::time::Duration::from_secs(delay_secs)).await;
+                if let Some(delay) = retry_after(response.headers()) {
+                    debug!("Received RETRY_AFTER header. Retrying after {:?}", delay);
+                    tokio::time::sleep(delay).await;
                 } else if retries < max_retries {
                     let backoff_delay = 2u64.pow(retries).min(60);
                     debug!(
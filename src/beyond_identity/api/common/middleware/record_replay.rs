@@ -0,0 +1,260 @@
+use http::Extensions;
+use log::debug;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Error, Middleware, Next, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// Whether HTTP exchanges are recorded to, or replayed from, a directory.
+#[derive(Debug, Clone)]
+pub enum RecordReplayMode {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+// Mode set from the command line for this process
+static MODE: OnceLock<RecordReplayMode> = OnceLock::new();
+
+// Sequence number of the next recording
+static NEXT_RECORDING: OnceLock<AtomicUsize> = OnceLock::new();
+
+// Recordings loaded for replay, with whether each has been served yet
+static RECORDINGS: OnceLock<Mutex<Vec<(Exchange, bool)>>> = OnceLock::new();
+
+/// Placeholder that replaces redacted secrets.
+pub const REDACTED: &str = "[REDACTED]";
+
+const SENSITIVE_HEADERS: &[&str] = &["authorization", "cookie", "set-cookie", "x-api-key"];
+
+const SENSITIVE_FIELDS: &[&str] = &[
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "client_secret",
+    "api_key",
+    "password",
+    "secret",
+    "token",
+];
+
+/// A single recorded request and its response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Exchange {
+    method: String,
+    path: String,
+    request_headers: BTreeMap<String, String>,
+    request_body: Option<Value>,
+    status: u16,
+    response_headers: BTreeMap<String, String>,
+    response_body: Value,
+}
+
+/// Records every exchange to numbered JSON files with secrets redacted, or
+/// serves responses from such files instead of the network. Recordings are
+/// matched on method, path and query, and served in the order they were
+/// recorded. Without a mode set, requests pass through untouched.
+pub struct RecordReplayMiddleware;
+
+impl RecordReplayMiddleware {
+    // Set the mode used by every client created in this process
+    pub fn configure(mode: RecordReplayMode) {
+        _ = MODE.set(mode);
+    }
+
+    // Whether responses are served from recordings. Nothing they contain may
+    // be stored locally, e.g. as a cached token or a history entry.
+    pub fn replaying() -> bool {
+        matches!(MODE.get(), Some(RecordReplayMode::Replay(_)))
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for RecordReplayMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        match MODE.get() {
+            None => next.run(req, extensions).await,
+            Some(RecordReplayMode::Replay(dir)) => replay(dir, &req),
+            Some(RecordReplayMode::Record(dir)) => {
+                let method = req.method().to_string();
+                let path = path_and_query(&req);
+                let request_headers = redact_headers(req.headers());
+                let request_body = req.body().and_then(|b| b.as_bytes()).map(body_value);
+
+                let response = next.run(req, extensions).await?;
+
+                let status = response.status();
+                let headers = response.headers().clone();
+                let version = response.version();
+                let bytes = response.bytes().await?;
+
+                let exchange = Exchange {
+                    method,
+                    path,
+                    request_headers,
+                    request_body,
+                    status: status.as_u16(),
+                    response_headers: redact_headers(&headers),
+                    response_body: body_value(&bytes),
+                };
+                write_recording(dir, &exchange)?;
+
+                let mut rebuilt = http::Response::builder().status(status).version(version);
+                for (name, value) in headers.iter() {
+                    rebuilt = rebuilt.header(name, value);
+                }
+                Ok(Response::from(
+                    rebuilt.body(bytes).map_err(middleware_error)?,
+                ))
+            }
+        }
+    }
+}
+
+fn middleware_error(e: impl std::fmt::Display) -> Error {
+    Error::Middleware(anyhow::anyhow!(e.to_string()))
+}
+
+fn path_and_query(req: &Request) -> String {
+    let url = req.url();
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+fn redact_headers(headers: &http::HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                value.to_str().unwrap_or_default().to_string()
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+// Bodies are stored as JSON with secrets redacted, or as a string if they are not JSON
+fn body_value(bytes: &[u8]) -> Value {
+    match serde_json::from_slice::<Value>(bytes) {
+        Ok(mut value) => {
+            redact_value(&mut value);
+            value
+        }
+        Err(_) => Value::String(String::from_utf8_lossy(bytes).to_string()),
+    }
+}
+
+/// Replace the values of fields holding secrets, at any depth.
+pub fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                if SENSITIVE_FIELDS.contains(&key.to_lowercase().as_str()) {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+fn write_recording(dir: &Path, exchange: &Exchange) -> Result<()> {
+    std::fs::create_dir_all(dir).map_err(middleware_error)?;
+    // Continue numbering after any recordings already in the directory
+    let counter = NEXT_RECORDING.get_or_init(|| {
+        AtomicUsize::new(
+            recording_files(dir)
+                .iter()
+                .filter_map(|p| p.file_stem()?.to_str()?.parse::<usize>().ok())
+                .max()
+                .map_or(1, |n| n + 1),
+        )
+    });
+    let path = dir.join(format!(
+        "{:05}.json",
+        counter.fetch_add(1, Ordering::SeqCst)
+    ));
+    debug!(
+        "Recording {} {} to {}",
+        exchange.method,
+        exchange.path,
+        path.display()
+    );
+    let contents = serde_json::to_vec_pretty(exchange).map_err(middleware_error)?;
+    std::fs::write(&path, contents).map_err(middleware_error)
+}
+
+fn recording_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+fn replay(dir: &Path, req: &Request) -> Result<Response> {
+    let recordings = RECORDINGS.get_or_init(|| {
+        Mutex::new(
+            recording_files(dir)
+                .iter()
+                .filter_map(|path| {
+                    let contents = std::fs::read_to_string(path).ok()?;
+                    serde_json::from_str::<Exchange>(&contents).ok()
+                })
+                .map(|exchange| (exchange, false))
+                .collect(),
+        )
+    });
+
+    let method = req.method().to_string();
+    let path = path_and_query(req);
+    let mut recordings = recordings.lock().map_err(middleware_error)?;
+    let (exchange, served) = recordings
+        .iter_mut()
+        .find(|(e, served)| !served && e.method == method && e.path == path)
+        .ok_or_else(|| {
+            middleware_error(format!(
+                "No recorded response for {} {} in {}",
+                method,
+                path,
+                dir.display()
+            ))
+        })?;
+    *served = true;
+    debug!("Replaying {} {}", method, path);
+
+    let body = match &exchange.response_body {
+        Value::String(s) => s.clone().into_bytes(),
+        value => serde_json::to_vec(value).map_err(middleware_error)?,
+    };
+    let mut response = http::Response::builder().status(exchange.status);
+    for (name, value) in &exchange.response_headers {
+        // The stored body may differ in length from the original
+        if name != "content-length" {
+            response = response.header(name, value);
+        }
+    }
+    Ok(Response::from(
+        response.body(body).map_err(middleware_error)?,
+    ))
+}
@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+use http::Extensions;
+use log::debug;
+use rand::Rng;
+use reqwest::{header::HeaderMap, Method, Request, Response};
+use reqwest_middleware::{Error, Middleware, Next, Result};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Limits on how often and for how long failed requests are retried.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Maximum number of retries of a single request
+    pub max_retries: u32,
+    /// Total time a single request may spend waiting between retries
+    pub budget: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            budget: Duration::from_secs(300),
+        }
+    }
+}
+
+// Policy set from the command line for this process
+static RETRY_POLICY: OnceLock<RetryPolicy> = OnceLock::new();
+
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Retries requests that failed with a 5xx status or a connect/timeout error,
+/// using jittered exponential backoff. Only idempotent methods are retried
+/// after the request may have reached the server. PATCH counts as idempotent
+/// since Beyond Identity PATCH bodies set absolute field values.
+pub struct RetryMiddleware;
+
+impl RetryMiddleware {
+    // Set the retry policy used by every client created in this process
+    pub fn configure(policy: RetryPolicy) {
+        _ = RETRY_POLICY.set(policy);
+    }
+
+    fn policy() -> RetryPolicy {
+        RETRY_POLICY.get().copied().unwrap_or_default()
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for RetryMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let policy = Self::policy();
+        let idempotent = is_idempotent(req.method());
+        let started = Instant::now();
+        let mut retries = 0;
+
+        loop {
+            let duplicate_request = req.try_clone().ok_or_else(|| {
+                Error::Middleware(anyhow::anyhow!(
+                    "Request object is not clonable. Are you passing a streaming body?".to_string()
+                ))
+            })?;
+
+            let result = next.clone().run(duplicate_request, extensions).await;
+
+            let delay = match &result {
+                Ok(response) if idempotent && response.status().is_server_error() => {
+                    debug!("Received {} for {}", response.status(), req.url());
+                    retry_after(response.headers()).unwrap_or_else(|| backoff(retries))
+                }
+                Err(Error::Reqwest(e)) if e.is_connect() || (idempotent && e.is_timeout()) => {
+                    debug!("Request to {} failed: {}", req.url(), e);
+                    backoff(retries)
+                }
+                _ => return result,
+            };
+
+            if retries >= policy.max_retries || started.elapsed() + delay > policy.budget {
+                debug!(
+                    "Giving up on {} after {} retries and {:?}",
+                    req.url(),
+                    retries,
+                    started.elapsed()
+                );
+                return result;
+            }
+
+            debug!("Retrying {} after {:?}", req.url(), delay);
+            tokio::time::sleep(delay).await;
+            retries += 1;
+        }
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE | Method::PATCH
+    )
+}
+
+// Exponential backoff with full jitter
+fn backoff(retries: u32) -> Duration {
+    let cap = BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(retries))
+        .min(MAX_DELAY);
+    cap.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+}
+
+/// Parse a `Retry-After` header given either as a number of seconds or as an HTTP date.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
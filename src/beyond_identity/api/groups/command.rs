@@ -4,16 +4,26 @@ use super::types::{
 };
 
 use crate::beyond_identity::api::common::filter::Filter;
+use crate::beyond_identity::api::common::middleware::dry_run::DryRunMiddleware;
 use crate::beyond_identity::api::common::serialize::output;
-use crate::beyond_identity::api::common::service::GroupsService;
+use crate::beyond_identity::api::common::service::{GroupsService, IdentitiesService};
+use crate::beyond_identity::api::identities::api::IdentitiesApi;
+use crate::beyond_identity::api::identities::types::Identity;
+use crate::beyond_identity::helper::groups::{
+    add_members_in_batches, delete_members_in_batches, find_or_create_group,
+};
 use crate::common::command::ambassador_impl_Executable;
 use crate::common::command::Executable;
+use crate::common::csv;
 use crate::common::error::BiError;
 
 use async_trait::async_trait;
-use clap::{Args, Subcommand};
+use clap::{Args, Subcommand, ValueEnum};
 use field_types::FieldName;
 use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 // ====================================
 // Groups Commands
@@ -40,6 +50,8 @@ pub enum GroupCommands {
     ListMembers(ListMembers),
     /// List role memberships for a group
     ListRoles(ListRoles),
+    /// Make a group's members match a CSV roster, adding and removing only the difference
+    Sync(SyncMembers),
 }
 
 // ====================================
@@ -250,3 +262,241 @@ impl Executable for ListRoles {
         .await
     }
 }
+
+// ====================================
+// Groups Sync
+// ====================================
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum SyncKey {
+    /// Primary email address, compared case-insensitively
+    Email,
+    /// Username, compared case-insensitively
+    Username,
+    /// External ID
+    ExternalId,
+}
+
+impl SyncKey {
+    // CSV header names accepted for the key column
+    fn column_names(&self) -> &'static [&'static str] {
+        match self {
+            SyncKey::Email => &["email", "primary_email_address", "email_address"],
+            SyncKey::Username => &["username", "user_name"],
+            SyncKey::ExternalId => &["external_id", "external-id", "externalid"],
+        }
+    }
+
+    fn normalize(&self, value: &str) -> String {
+        match self {
+            SyncKey::Email | SyncKey::Username => value.trim().to_lowercase(),
+            SyncKey::ExternalId => value.trim().to_string(),
+        }
+    }
+
+    fn value_of(&self, identity: &Identity) -> Option<String> {
+        match self {
+            SyncKey::Email => identity.traits.primary_email_address.clone(),
+            SyncKey::Username => Some(identity.traits.username.clone()),
+            SyncKey::ExternalId => identity.traits.external_id.clone(),
+        }
+        .map(|value| self.normalize(&value))
+        .filter(|value| !value.is_empty())
+    }
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SyncMembers {
+    /// ID or display name of the Group to sync
+    group: String,
+
+    /// CSV roster of the members. A header row naming the key column (e.g. `email`) is
+    /// used when present, otherwise the first column holds the keys.
+    #[clap(long, value_name = "FILE")]
+    file: PathBuf,
+
+    /// Identity attribute the roster is keyed on
+    #[clap(long, value_enum)]
+    key: SyncKey,
+
+    /// Create a group with the given display name when no group matches it
+    #[clap(long)]
+    create_missing: bool,
+
+    /// Refuse to sync when more than this many members would be removed
+    #[clap(long, default_value_t = 10)]
+    max_removals: usize,
+
+    /// Sync even when roster rows can't be resolved to a single identity or more than
+    /// --max-removals members would be removed
+    #[clap(long)]
+    force: bool,
+
+    /// Sync without asking for confirmation
+    #[clap(long)]
+    yes: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncResult {
+    group_id: String,
+    created: bool,
+    added: Vec<String>,
+    removed: Vec<String>,
+    unchanged: usize,
+    /// Roster values that match no identity or more than one
+    unresolved: Vec<String>,
+}
+
+// Reads the normalized key values of a roster
+fn read_roster(file: &Path, key: SyncKey) -> Result<Vec<String>, BiError> {
+    let mut records = csv::read(file)?;
+    let column = match records.first().and_then(|header| {
+        header
+            .iter()
+            .position(|f| key.column_names().contains(&f.to_lowercase().as_str()))
+    }) {
+        Some(column) => {
+            records.remove(0);
+            column
+        }
+        None => 0,
+    };
+    Ok(records
+        .iter()
+        .filter_map(|record| record.get(column))
+        .map(|value| key.normalize(value))
+        .filter(|value| !value.is_empty())
+        .collect())
+}
+
+impl SyncMembers {
+    async fn sync(&self) -> Result<SyncResult, BiError> {
+        let roster = read_roster(&self.file, self.key)?;
+
+        let mut identity_ids: HashMap<String, Vec<String>> = HashMap::new();
+        for identity in IdentitiesService::new()
+            .build()
+            .await
+            .list_identities(None, None)
+            .await?
+            .identities
+        {
+            if let Some(value) = self.key.value_of(&identity) {
+                identity_ids.entry(value).or_default().push(identity.id);
+            }
+        }
+        let mut desired = HashSet::new();
+        let mut unresolved = Vec::new();
+        for value in roster {
+            match identity_ids.get(&value).map(Vec::as_slice) {
+                Some([identity_id]) => {
+                    desired.insert(identity_id.clone());
+                }
+                _ => unresolved.push(value),
+            }
+        }
+        unresolved.sort();
+        unresolved.dedup();
+
+        let groups_service = GroupsService::new().build().await;
+        let groups = groups_service.list_groups(None, None).await?.groups;
+        let existing = groups
+            .iter()
+            .find(|g| g.id == self.group)
+            .or_else(|| groups.iter().find(|g| g.display_name == self.group));
+        if existing.is_none() && !self.create_missing {
+            return Err(BiError::StringError(format!(
+                "No group with the ID or display name {}. Pass --create-missing to create it.",
+                self.group
+            )));
+        }
+
+        let current: HashSet<String> = match existing {
+            Some(group) => groups_service
+                .list_members(&group.id, None)
+                .await?
+                .identities
+                .into_iter()
+                .map(|identity| identity.id)
+                .collect(),
+            None => HashSet::new(),
+        };
+
+        let mut added: Vec<String> = desired.difference(&current).cloned().collect();
+        let mut removed: Vec<String> = current.difference(&desired).cloned().collect();
+        added.sort();
+        removed.sort();
+
+        // A roster that doesn't resolve, e.g. because of a wrong --key or an
+        // unrecognized header, would otherwise empty the group
+        if !self.force {
+            if !unresolved.is_empty() {
+                return Err(BiError::StringError(format!(
+                    "{} roster values match no identity or more than one, e.g. {}. Nothing was changed. Pass --force to sync anyway.",
+                    unresolved.len(),
+                    unresolved[0]
+                )));
+            }
+            if removed.len() > self.max_removals {
+                return Err(BiError::StringError(format!(
+                    "{} members would be removed, more than --max-removals {}. Nothing was changed. Pass --force to sync anyway.",
+                    removed.len(),
+                    self.max_removals
+                )));
+            }
+        }
+
+        eprintln!(
+            "{} members will be added to and {} removed from group {}{}.",
+            added.len(),
+            removed.len(),
+            self.group,
+            if existing.is_none() { " (created)" } else { "" }
+        );
+        let unchanged = added.is_empty() && removed.is_empty();
+        if !self.yes && !DryRunMiddleware::enabled() && !unchanged {
+            eprint!("Sync them? [y/N]: ");
+            io::stderr().flush().unwrap();
+            let mut input = String::new();
+            io::stdin().read_line(&mut input).unwrap();
+            if !input.trim().eq_ignore_ascii_case("y") {
+                return Err(BiError::StringError(
+                    "Sync cancelled. Nothing was changed.".to_string(),
+                ));
+            }
+        }
+
+        let (group_id, created) = match existing {
+            Some(group) => (group.id.clone(), false),
+            None => {
+                let group = find_or_create_group(
+                    &groups_service,
+                    &groups,
+                    &self.group,
+                    &format!("Members synced from {}", self.file.display()),
+                )
+                .await?;
+                (group.id, true)
+            }
+        };
+        add_members_in_batches(&groups_service, &group_id, &added).await?;
+        delete_members_in_batches(&groups_service, &group_id, &removed).await?;
+
+        Ok(SyncResult {
+            group_id,
+            created,
+            unchanged: desired.intersection(&current).count(),
+            added,
+            removed,
+            unresolved,
+        })
+    }
+}
+
+#[async_trait]
+impl Executable for SyncMembers {
+    async fn execute(&self) -> Result<(), BiError> {
+        output(self.sync()).await
+    }
+}
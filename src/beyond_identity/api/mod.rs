@@ -5,5 +5,6 @@ pub mod credentials;
 pub mod groups;
 pub mod identities;
 pub mod realms;
+pub mod request;
 pub mod roles;
 pub mod tenants;
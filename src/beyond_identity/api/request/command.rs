@@ -0,0 +1,147 @@
+use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::beyond_identity::api::common::serialize::output;
+use crate::common::command::Executable;
+use crate::common::error::BiError;
+
+use async_trait::async_trait;
+use clap::Args;
+use http::Method;
+use serde_json::Value;
+use std::io::Read;
+use url::Url;
+
+// ====================================
+// Raw API Request
+// ====================================
+
+/// Send an arbitrary request to the Beyond Identity API of the default tenant/realm.
+/// Authentication, rate limiting and retries are handled the same way as for every other command.
+///
+/// Example:
+///
+///   bi api request GET /v1/tenants/{tenant}/realms/{realm}/identities --query page_size=10
+#[derive(Args, Debug, Clone)]
+pub struct Request {
+    /// HTTP method, e.g. GET, POST, PATCH, DELETE
+    method: String,
+
+    /// Path relative to the API base URL. `{tenant}` and `{realm}` are replaced
+    /// with the IDs of the default tenant/realm.
+    path: String,
+
+    /// JSON request body. Use `@file.json` to read it from a file, or `@-` for stdin.
+    #[clap(long)]
+    data: Option<String>,
+
+    /// Query parameter as `key=value`. Can be repeated.
+    #[clap(long = "query", value_parser = parse_query_param)]
+    query: Vec<(String, String)>,
+
+    /// Follow `next_page_token` and merge the items of all pages into a single response
+    #[clap(long)]
+    paginate: bool,
+}
+
+fn parse_query_param(param: &str) -> Result<(String, String), String> {
+    param
+        .split_once('=')
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .ok_or_else(|| format!("Expected key=value, got '{}'", param))
+}
+
+fn read_body(data: &str) -> Result<Value, BiError> {
+    let raw = match data.strip_prefix('@') {
+        Some("-") => {
+            let mut buf = String::new();
+            std::io::stdin()
+                .read_to_string(&mut buf)
+                .map_err(|e| BiError::StringError(e.to_string()))?;
+            buf
+        }
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| BiError::StringError(format!("Failed to read {}: {}", path, e)))?,
+        None => data.to_string(),
+    };
+    Ok(serde_json::from_str(&raw)?)
+}
+
+#[async_trait]
+impl Executable for Request {
+    async fn execute(&self) -> Result<(), BiError> {
+        let method = Method::from_bytes(self.method.to_uppercase().as_bytes())
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+        let body = self.data.as_deref().map(read_body).transpose()?;
+
+        let api_client = ApiClient::new(None, None).await;
+        let url = api_client
+            .builder()
+            .await?
+            .api()
+            .add_template_path(&self.path)
+            .to_string()?;
+        let mut url = Url::parse(&url).map_err(BiError::InvalidUrl)?;
+        for (key, value) in &self.query {
+            url.query_pairs_mut().append_pair(key, value);
+        }
+
+        if self.paginate {
+            output(send_paginated(&api_client, method, url, body.as_ref())).await
+        } else {
+            output(api_client.send_request::<Value, Value>(method, url.as_str(), body.as_ref()))
+                .await
+        }
+    }
+}
+
+// Follow next_page_token, appending the items of every page to the array
+// field of the first response.
+async fn send_paginated(
+    api_client: &ApiClient,
+    method: Method,
+    url: Url,
+    body: Option<&Value>,
+) -> Result<Value, BiError> {
+    let mut merged: Option<Value> = None;
+    let mut page_token: Option<String> = None;
+
+    loop {
+        let mut page_url = url.clone();
+        if let Some(token) = &page_token {
+            page_url.query_pairs_mut().append_pair("page_token", token);
+        }
+
+        let mut page: Value = api_client
+            .send_request(method.clone(), page_url.as_str(), body)
+            .await?;
+        page_token = page
+            .get("next_page_token")
+            .and_then(Value::as_str)
+            .filter(|token| !token.is_empty())
+            .map(str::to_string);
+
+        match merged.as_mut() {
+            None => merged = Some(page),
+            Some(merged) => {
+                if let (Value::Object(merged), Value::Object(page)) = (merged, &mut page) {
+                    for (key, value) in page.iter_mut() {
+                        if let (Some(Value::Array(items)), Value::Array(more)) =
+                            (merged.get_mut(key), value)
+                        {
+                            items.append(more);
+                        }
+                    }
+                }
+            }
+        }
+
+        if page_token.is_none() {
+            break;
+        }
+    }
+
+    let mut merged = merged.unwrap_or(Value::Null);
+    if let Value::Object(merged) = &mut merged {
+        merged.remove("next_page_token");
+    }
+    Ok(merged)
+}
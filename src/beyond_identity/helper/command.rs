@@ -1,23 +1,48 @@
 use super::admin::{create_admin_account, get_identities_without_role};
+use super::credentials::{
+    build_credentials_report, credentials_report_csv, fetch_credentials,
+    fetch_identity_credentials, is_active, parse_created_before, read_revocation_targets,
+    revocation_results_csv, revoke_credentials, ReportFormat, RevocationResult, RevocationTarget,
+};
+use super::duplicates::{
+    duplicate_rows, duplicate_rows_csv, find_duplicate_clusters, merge_identities,
+};
 use super::enrollment::{
     get_all_identities, get_send_email_payload, get_unenrolled_identities, select_group,
     select_identities, send_enrollment_email,
 };
 use super::groups::get_unenrolled_identities_from_group;
-use super::identities::{
-    delete_all_identities, delete_norole_identities, delete_unenrolled_identities,
-};
+use super::identities::write_identities_snapshot;
+use super::offboard::{offboard_identity, print_receipt, reinstate_identity, resolve_identity};
 
 use crate::beyond_identity::api::common::api_client::ApiClient;
-use crate::beyond_identity::api::common::service::{GroupsService, IdentitiesService};
+use crate::beyond_identity::api::common::filter::Filter;
+use crate::beyond_identity::api::common::middleware::dry_run::DryRunMiddleware;
+use crate::beyond_identity::api::common::service::{
+    GroupsService, IdentitiesService, RealmsService,
+};
 use crate::beyond_identity::api::groups::api::GroupsApi;
 use crate::beyond_identity::api::identities::api::IdentitiesApi;
-use crate::beyond_identity::api::identities::types::Identity;
+use crate::beyond_identity::api::identities::types::{Identity, Status};
+use crate::beyond_identity::api::realms::api::RealmsApi;
 use crate::common::command::ambassador_impl_Executable;
+use crate::common::database::models::Offboarding;
+use crate::common::database::{Database, PROTECTED_TAG};
 use crate::common::{command::Executable, error::BiError};
 
 use async_trait::async_trait;
 use clap::{ArgGroup, Args, Subcommand};
+use reqwest::StatusCode;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use tabled::settings::style::Style;
+use tabled::Table;
+
+// Number of targeted identities listed before a bulk delete
+const DELETE_SAMPLE_SIZE: usize = 10;
+
+// Number of targeted credentials listed before a bulk revocation
+const REVOKE_SAMPLE_SIZE: usize = 10;
 
 /// Helper commands for managing administrative and user-related actions within Beyond Identity.
 #[derive(Subcommand, ambassador::Delegate)]
@@ -28,6 +53,10 @@ pub enum BeyondIdentityHelperCommands {
 
     /// Deletes all identities from a realm in case you want to set them up from scratch.
     /// The identities are unassigned from roles and groups automatically.
+    ///
+    /// Before deleting, the number of targeted identities and a sample of them are
+    /// printed, the targets are written to a snapshot file in the data directory, and
+    /// the realm's display name has to be typed to confirm.
     #[command(group = ArgGroup::new("delete_option").required(true).multiple(false))]
     DeleteAllIdentities(DeleteAllIdentities),
 
@@ -37,6 +66,37 @@ pub enum BeyondIdentityHelperCommands {
 
     /// Get a list of identities who have not enrolled yet (identities without a passkey).
     ReviewUnenrolled(ReviewUnenrolled),
+
+    /// Offboards identities: suspends them, revokes all their credentials and removes
+    /// them from their groups and roles. A receipt of every step is printed and kept,
+    /// so the offboarding can be reversed with `reinstate`.
+    Offboard(Offboard),
+
+    /// Reverses an offboarding: restores the identity's status and its group and role
+    /// memberships. Revoked credentials can't be restored.
+    Reinstate(Reinstate),
+
+    /// Deletes offboarded identities whose grace period has ended.
+    PurgeOffboarded(PurgeOffboarded),
+
+    /// Lists every credential of the realm together with its identity and flags
+    /// identities with several active credentials, stale credentials, identities with
+    /// only revoked credentials and suspended identities that still hold active ones.
+    CredentialsReport(CredentialsReport),
+
+    /// Revokes the active credentials of every identity matching a SCIM filter, of
+    /// the members of a group or of the identities listed in a CSV file. The results
+    /// are written to a CSV file.
+    #[command(group = ArgGroup::new("revoke_source").required(true).multiple(false))]
+    RevokeCredentials(RevokeCredentials),
+
+    /// Reports clusters of identities sharing an email address, username or external ID,
+    /// compared case-insensitively.
+    FindDuplicates(FindDuplicates),
+
+    /// Moves the group, role and SSO config memberships of a duplicate identity onto the
+    /// identity to keep, then suspends or deletes the duplicate.
+    Merge(Merge),
 }
 
 #[derive(Args)]
@@ -56,9 +116,22 @@ pub struct DeleteAllIdentities {
     #[arg(long, group = "delete_option")]
     unenrolled: bool,
 
-    /// Skip validation when deleting identities.
+    /// Delete every matching identity instead of selecting them interactively.
+    /// The realm's display name still has to be confirmed.
     #[arg(long)]
     force: bool,
+
+    /// Refuse to delete anything if more than this many identities are targeted.
+    #[arg(long)]
+    max_deletes: Option<usize>,
+
+    /// Confirm with the realm's display name instead of typing it, e.g. in scripts.
+    #[arg(long, value_name = "REALM_DISPLAY_NAME")]
+    confirm: Option<String>,
+
+    /// Allow deleting from a realm tagged "protected" with `config tenants tag`.
+    #[arg(long)]
+    i_know_what_im_doing: bool,
 }
 
 #[derive(Args)]
@@ -174,25 +247,18 @@ impl Executable for SendEnrollmentEmail {
 impl Executable for DeleteAllIdentities {
     async fn execute(&self) -> Result<(), BiError> {
         let api_client = ApiClient::new(None, None).await;
-        if self.force {
-            if self.all {
-                delete_all_identities(&api_client)
-                    .await
-                    .expect("Failed to delete all identities");
-            }
-
-            if self.unenrolled {
-                delete_unenrolled_identities(&api_client)
-                    .await
-                    .expect("Failed to delete unenrolled identities");
-            }
+        let (tenant, realm) = api_client
+            .db
+            .get_default_tenant_and_realm()
+            .await?
+            .ok_or_else(|| BiError::StringError("No default tenant/realm set".to_string()))?;
 
-            if self.norole {
-                delete_norole_identities(&api_client)
-                    .await
-                    .expect("Failed to delete norole identities");
-            }
-            return Ok(());
+        let tags = api_client.db.get_realm_tags(&tenant.id, &realm.id).await?;
+        if tags.iter().any(|t| t == PROTECTED_TAG) && !self.i_know_what_im_doing {
+            return Err(BiError::StringError(format!(
+                "Tenant {} realm {} is tagged \"{}\". Pass --i-know-what-im-doing to delete from it anyway.",
+                tenant.id, realm.id, PROTECTED_TAG
+            )));
         }
 
         let mut identities = vec![];
@@ -215,12 +281,86 @@ impl Executable for DeleteAllIdentities {
                 .expect("Failed to fetch unenrolled identities");
         }
 
-        if identities.len() == 0 {
+        if identities.is_empty() {
             println!("No identities found.");
             return Ok(());
         }
 
-        let selected_identities = select_identities(&identities);
+        let selected_identities = if self.force {
+            identities
+        } else {
+            select_identities(&identities)
+        };
+
+        if selected_identities.is_empty() {
+            println!("No identities selected.");
+            return Ok(());
+        }
+
+        if let Some(max) = self.max_deletes {
+            if selected_identities.len() > max {
+                return Err(BiError::StringError(format!(
+                    "{} identities are targeted, more than --max-deletes {}. Nothing was deleted.",
+                    selected_identities.len(),
+                    max
+                )));
+            }
+        }
+
+        let realm_name = RealmsService::new()
+            .build()
+            .await
+            .get_realm(&realm.id)
+            .await?
+            .display_name;
+
+        println!(
+            "{} identities will be deleted from realm \"{}\" ({}) of tenant {}, e.g.:",
+            selected_identities.len(),
+            realm_name,
+            realm.id,
+            tenant.id
+        );
+        for identity in selected_identities.iter().take(DELETE_SAMPLE_SIZE) {
+            println!(
+                "  {} - {}",
+                identity.id,
+                identity
+                    .traits
+                    .primary_email_address
+                    .as_deref()
+                    .unwrap_or(&identity.traits.username)
+            );
+        }
+        if selected_identities.len() > DELETE_SAMPLE_SIZE {
+            println!(
+                "  ... and {} more",
+                selected_identities.len() - DELETE_SAMPLE_SIZE
+            );
+        }
+
+        let snapshot = write_identities_snapshot(&tenant.id, &realm.id, &selected_identities)?;
+        println!(
+            "Wrote a snapshot of the identities to {}",
+            snapshot.display()
+        );
+
+        let confirmation = match &self.confirm {
+            Some(confirmation) => confirmation.clone(),
+            None => {
+                print!("Type the realm's display name to confirm: ");
+                io::stdout().flush().unwrap();
+                let mut input = String::new();
+                io::stdin().read_line(&mut input).unwrap();
+                input.trim().to_string()
+            }
+        };
+        if confirmation != realm_name {
+            return Err(BiError::StringError(format!(
+                "\"{}\" does not match the realm's display name. Nothing was deleted.",
+                confirmation
+            )));
+        }
 
         for identity in &selected_identities {
             IdentitiesService::new()
@@ -261,3 +401,681 @@ impl Executable for ReviewUnenrolled {
         Ok(())
     }
 }
+
+#[derive(Args)]
+pub struct Offboard {
+    /// ID, email address or username of the identity
+    #[arg(required_unless_present = "batch", conflicts_with = "batch")]
+    identity: Option<String>,
+
+    /// File listing one identity ID, email address or username per line.
+    /// Empty lines and lines starting with `#` are skipped.
+    #[arg(long, value_name = "FILE")]
+    batch: Option<PathBuf>,
+
+    /// Delete the identities with `purge-offboarded` once this many days have passed
+    #[arg(long, value_name = "DAYS")]
+    delete_after_days: Option<u32>,
+}
+
+#[async_trait]
+impl Executable for Offboard {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        let (tenant, realm) = api_client
+            .db
+            .get_default_tenant_and_realm()
+            .await?
+            .ok_or_else(|| BiError::StringError("No default tenant/realm set".to_string()))?;
+
+        let targets: Vec<String> = match (&self.identity, &self.batch) {
+            (Some(identity), _) => vec![identity.clone()],
+            (None, Some(path)) => std::fs::read_to_string(path)
+                .map_err(|e| {
+                    BiError::StringError(format!("Failed to read {}: {}", path.display(), e))
+                })?
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string)
+                .collect(),
+            (None, None) => unreachable!(),
+        };
+
+        let mut offboarded = 0;
+        for target in &targets {
+            let identity = match resolve_identity(target).await {
+                Ok(identity) => identity,
+                Err(err) => {
+                    println!("Skipping {}: {}\n", target, err);
+                    continue;
+                }
+            };
+
+            // Offboarding twice would record a receipt with nothing to restore
+            let open = api_client
+                .db
+                .get_open_offboardings(&tenant.id, &realm.id, &identity.id)
+                .await?;
+            if let Some(offboarding) = open.first() {
+                println!(
+                    "Skipping {}: already offboarded on {}. Reinstate it first to offboard it again.\n",
+                    target,
+                    chrono::DateTime::from_timestamp(offboarding.offboarded_at, 0)
+                        .map(|d| d.to_rfc3339())
+                        .unwrap_or_else(|| offboarding.offboarded_at.to_string())
+                );
+                continue;
+            }
+
+            let receipt = offboard_identity(&api_client, &tenant, &realm, &identity).await?;
+            let now = chrono::Utc::now().timestamp();
+            let delete_after = self
+                .delete_after_days
+                .map(|days| now + i64::from(days) * 24 * 60 * 60);
+            if !DryRunMiddleware::enabled() {
+                api_client
+                    .db
+                    .add_offboarding(&Offboarding {
+                        id: 0,
+                        tenant_id: tenant.id.clone(),
+                        realm_id: realm.id.clone(),
+                        identity_id: identity.id.clone(),
+                        offboarded_at: now,
+                        receipt: serde_json::to_string(&receipt)?,
+                        delete_after,
+                        deleted_at: None,
+                        reinstated_at: None,
+                    })
+                    .await?;
+            }
+            print_receipt(&receipt, delete_after);
+            println!();
+            offboarded += 1;
+        }
+
+        if targets.len() > 1 {
+            println!("Offboarded {} of {} identities.", offboarded, targets.len());
+        }
+        if offboarded > 0 {
+            println!("Reverse an offboarding with `bi helper reinstate <identity>`.");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct Reinstate {
+    /// ID, email address or username of the offboarded identity
+    identity: String,
+}
+
+#[async_trait]
+impl Executable for Reinstate {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        let (tenant, realm) = api_client
+            .db
+            .get_default_tenant_and_realm()
+            .await?
+            .ok_or_else(|| BiError::StringError("No default tenant/realm set".to_string()))?;
+
+        let identity = resolve_identity(&self.identity).await?;
+        let offboardings = api_client
+            .db
+            .get_open_offboardings(&tenant.id, &realm.id, &identity.id)
+            .await?;
+        if offboardings.is_empty() {
+            return Err(BiError::StringError(format!(
+                "{} has no offboarding that can be reversed",
+                self.identity
+            )));
+        }
+
+        // Reverse the most recent offboarding first, so the status recorded by
+        // the oldest one is restored last
+        let mut failures = Vec::new();
+        for offboarding in &offboardings {
+            failures.extend(reinstate_identity(&api_client, &tenant, &realm, offboarding).await?);
+            if !DryRunMiddleware::enabled() {
+                api_client
+                    .db
+                    .set_offboarding_reinstated(offboarding.id)
+                    .await?;
+            }
+        }
+        println!("Reinstated {}.", identity.id);
+        for failure in failures {
+            println!("  ! {}", failure);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct PurgeOffboarded {
+    /// Delete without asking for confirmation
+    #[arg(long)]
+    yes: bool,
+
+    /// Allow deleting from a realm tagged "protected" with `config tenants tag`.
+    #[arg(long)]
+    i_know_what_im_doing: bool,
+}
+
+#[async_trait]
+impl Executable for PurgeOffboarded {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        let (tenant, realm) = api_client
+            .db
+            .get_default_tenant_and_realm()
+            .await?
+            .ok_or_else(|| BiError::StringError("No default tenant/realm set".to_string()))?;
+
+        let tags = api_client.db.get_realm_tags(&tenant.id, &realm.id).await?;
+        if tags.iter().any(|t| t == PROTECTED_TAG) && !self.i_know_what_im_doing {
+            return Err(BiError::StringError(format!(
+                "Tenant {} realm {} is tagged \"{}\". Pass --i-know-what-im-doing to delete from it anyway.",
+                tenant.id, realm.id, PROTECTED_TAG
+            )));
+        }
+
+        let due = api_client
+            .db
+            .get_due_offboardings(&tenant.id, &realm.id, chrono::Utc::now().timestamp())
+            .await?;
+        if due.is_empty() {
+            println!("No offboarded identities are due for deletion.");
+            return Ok(());
+        }
+
+        // Only delete identities that are still suspended. Anyone reactivated
+        // since the offboarding is left alone.
+        let identities_service = IdentitiesService::new().build().await;
+        let mut targets = Vec::new();
+        for offboarding in due {
+            match identities_service
+                .get_identity(&offboarding.identity_id)
+                .await
+            {
+                Ok(identity) if matches!(identity.status, Some(Status::Suspended)) => {
+                    targets.push((offboarding, identity))
+                }
+                Ok(_) => println!(
+                    "Skipping identity {}: it is no longer suspended. Reinstate it to close its offboarding.",
+                    offboarding.identity_id
+                ),
+                Err(BiError::RequestError(StatusCode::NOT_FOUND, _)) => {
+                    if !DryRunMiddleware::enabled() {
+                        api_client
+                            .db
+                            .set_offboarding_deleted(offboarding.id)
+                            .await?;
+                    }
+                    println!("Identity {} was already deleted", offboarding.identity_id);
+                }
+                Err(err) => println!(
+                    "Skipping identity {}: {}",
+                    offboarding.identity_id, err
+                ),
+            }
+        }
+        if targets.is_empty() {
+            println!("No offboarded identities are due for deletion.");
+            return Ok(());
+        }
+
+        println!(
+            "{} offboarded identities will be deleted from realm {} of tenant {}, e.g.:",
+            targets.len(),
+            realm.id,
+            tenant.id
+        );
+        for (_, identity) in targets.iter().take(DELETE_SAMPLE_SIZE) {
+            println!(
+                "  {} - {}",
+                identity.id,
+                identity
+                    .traits
+                    .primary_email_address
+                    .as_deref()
+                    .unwrap_or(&identity.traits.username)
+            );
+        }
+        if targets.len() > DELETE_SAMPLE_SIZE {
+            println!("  ... and {} more", targets.len() - DELETE_SAMPLE_SIZE);
+        }
+
+        if !self.yes {
+            print!("Delete them? [y/N]: ");
+            io::stdout().flush().unwrap();
+            let mut input = String::new();
+            io::stdin().read_line(&mut input).unwrap();
+            if !input.trim().eq_ignore_ascii_case("y") {
+                println!("Nothing was deleted.");
+                return Ok(());
+            }
+        }
+
+        for (offboarding, identity) in &targets {
+            match identities_service.delete_identity(&identity.id).await {
+                Ok(_) => {
+                    if !DryRunMiddleware::enabled() {
+                        api_client
+                            .db
+                            .set_offboarding_deleted(offboarding.id)
+                            .await?;
+                    }
+                    println!("Deleted identity {}", identity.id);
+                }
+                Err(err) => println!("Failed to delete identity {}: {}", identity.id, err),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct CredentialsReport {
+    /// Output format
+    #[arg(long, value_enum, default_value = "table")]
+    format: ReportFormat,
+
+    /// Flag active credentials created more than this many days ago as stale
+    #[arg(long, value_name = "DAYS", default_value_t = 365)]
+    stale_after_days: i64,
+
+    /// Only list flagged credentials
+    #[arg(long)]
+    flagged_only: bool,
+}
+
+#[async_trait]
+impl Executable for CredentialsReport {
+    async fn execute(&self) -> Result<(), BiError> {
+        let identities = IdentitiesService::new()
+            .build()
+            .await
+            .list_identities(None, None)
+            .await?
+            .identities;
+        let mut identity_credentials = Vec::new();
+        for (identity, credentials) in fetch_identity_credentials(identities).await {
+            match credentials {
+                Ok(credentials) => identity_credentials.push((identity, credentials)),
+                Err(err) => eprintln!(
+                    "Failed to list the credentials of identity {}: {}",
+                    identity.id, err
+                ),
+            }
+        }
+        let mut rows = build_credentials_report(
+            &identity_credentials,
+            self.stale_after_days,
+            chrono::Utc::now(),
+        );
+        if self.flagged_only {
+            rows.retain(|row| !row.flags.is_empty());
+        }
+
+        match self.format {
+            ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+            ReportFormat::Csv => print!("{}", credentials_report_csv(&rows)),
+            ReportFormat::Table => {
+                let flagged = rows.iter().filter(|row| !row.flags.is_empty()).count();
+                let mut table = Table::new(&rows);
+                table.with(Style::extended());
+                println!("{}", table);
+                println!(
+                    "{} credentials of {} identities, {} flagged.",
+                    rows.len(),
+                    identity_credentials.len(),
+                    flagged
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct RevokeCredentials {
+    /// Revoke the credentials of identities matching this SCIM filter, e.g.
+    /// 'traits.primary_email_address co "@example.com"'
+    #[arg(long, group = "revoke_source")]
+    identity_filter: Option<String>,
+
+    /// Revoke the credentials of the members of this group
+    #[arg(long, group = "revoke_source")]
+    group_id: Option<String>,
+
+    /// CSV file of identity IDs, optionally followed by the ID of the only credential
+    /// to revoke. Files written by `credentials-report --format csv` can be used as is.
+    #[arg(long, group = "revoke_source", value_name = "FILE")]
+    file: Option<PathBuf>,
+
+    /// Only revoke credentials created before this date (YYYY-MM-DD) or RFC 3339 timestamp
+    #[arg(long, value_name = "DATE", value_parser = parse_created_before)]
+    created_before: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Maximum number of credentials revoked at the same time
+    #[arg(long, default_value_t = 8)]
+    concurrency: usize,
+
+    /// File the results are written to. Defaults to a timestamped file in the data directory.
+    #[arg(long, value_name = "FILE")]
+    results: Option<PathBuf>,
+
+    /// Revoke without asking for confirmation
+    #[arg(long)]
+    yes: bool,
+}
+
+#[async_trait]
+impl Executable for RevokeCredentials {
+    async fn execute(&self) -> Result<(), BiError> {
+        let targets: Vec<RevocationTarget> =
+            match (&self.identity_filter, &self.group_id, &self.file) {
+                (Some(filter), _, _) => IdentitiesService::new()
+                    .build()
+                    .await
+                    .list_identities(Filter::new(Some(filter.clone()))?, None)
+                    .await?
+                    .identities
+                    .into_iter()
+                    .map(|identity| RevocationTarget {
+                        identity_id: identity.id,
+                        credential_id: None,
+                    })
+                    .collect(),
+                (_, Some(group_id), _) => GroupsService::new()
+                    .build()
+                    .await
+                    .list_members(group_id, None)
+                    .await?
+                    .identities
+                    .into_iter()
+                    .map(|identity| RevocationTarget {
+                        identity_id: identity.id,
+                        credential_id: None,
+                    })
+                    .collect(),
+                (_, _, Some(path)) => {
+                    let targets = read_revocation_targets(path)?;
+                    if targets.skipped > 0 {
+                        println!(
+                            "Skipped {} rows of {} without an identity ID.",
+                            targets.skipped,
+                            path.display()
+                        );
+                    }
+                    targets.targets
+                }
+                _ => unreachable!(),
+            };
+
+        let mut identity_ids: Vec<String> = targets.iter().map(|t| t.identity_id.clone()).collect();
+        identity_ids.sort();
+        identity_ids.dedup();
+        // Identities whose credentials can't be listed are reported as failed
+        // instead of stopping the revocation of the others
+        let mut lookup_failures = Vec::new();
+        let mut credentials = Vec::new();
+        for (identity_id, result) in identity_ids
+            .iter()
+            .zip(fetch_credentials(&identity_ids).await)
+        {
+            match result {
+                Ok(found) => credentials.extend(found),
+                Err(err) => {
+                    println!(
+                        "Failed to list the credentials of identity {}: {}",
+                        identity_id, err
+                    );
+                    lookup_failures.push(RevocationResult {
+                        identity_id: identity_id.clone(),
+                        credential_id: String::new(),
+                        error: Some(format!("Failed to list credentials: {}", err)),
+                    });
+                }
+            }
+        }
+        let credentials: Vec<_> = credentials
+            .into_iter()
+            .filter(is_active)
+            .filter(|credential| {
+                targets.iter().any(|target| {
+                    target.identity_id == credential.identity_id
+                        && target
+                            .credential_id
+                            .as_ref()
+                            .is_none_or(|id| *id == credential.id)
+                })
+            })
+            .filter(|credential| match self.created_before {
+                Some(created_before) => {
+                    chrono::DateTime::parse_from_rfc3339(&credential.create_time)
+                        .is_ok_and(|created| created < created_before)
+                }
+                None => true,
+            })
+            .collect();
+
+        if credentials.is_empty() {
+            println!("No active credentials match.");
+            return self.write_results(&lookup_failures);
+        }
+
+        let identity_count = credentials
+            .iter()
+            .map(|c| &c.identity_id)
+            .collect::<std::collections::HashSet<_>>()
+            .len();
+        println!(
+            "{} active credentials of {} identities will be revoked, e.g.:",
+            credentials.len(),
+            identity_count
+        );
+        for credential in credentials.iter().take(REVOKE_SAMPLE_SIZE) {
+            println!(
+                "  {} of identity {} (created {})",
+                credential.id, credential.identity_id, credential.create_time
+            );
+        }
+        if credentials.len() > REVOKE_SAMPLE_SIZE {
+            println!("  ... and {} more", credentials.len() - REVOKE_SAMPLE_SIZE);
+        }
+
+        if !self.yes {
+            print!("Revoke them? [y/N]: ");
+            io::stdout().flush().unwrap();
+            let mut input = String::new();
+            io::stdin().read_line(&mut input).unwrap();
+            if !input.trim().eq_ignore_ascii_case("y") {
+                println!("Nothing was revoked.");
+                return Ok(());
+            }
+        }
+
+        let revoked = revoke_credentials(&credentials, self.concurrency).await;
+        let failed = revoked.iter().filter(|r| r.error.is_some()).count();
+        println!(
+            "Revoked {} of {} credentials.",
+            revoked.len() - failed,
+            revoked.len()
+        );
+        if !lookup_failures.is_empty() {
+            println!(
+                "The credentials of {} identities could not be listed.",
+                lookup_failures.len()
+            );
+        }
+
+        let mut results = lookup_failures;
+        results.extend(revoked);
+        self.write_results(&results)
+    }
+}
+
+impl RevokeCredentials {
+    fn write_results(&self, results: &[RevocationResult]) -> Result<(), BiError> {
+        if results.is_empty() || DryRunMiddleware::enabled() {
+            return Ok(());
+        }
+        let path = match &self.results {
+            Some(path) => path.clone(),
+            None => {
+                let dir = Database::data_dir()?.join("revocations");
+                std::fs::create_dir_all(&dir).map_err(|e| {
+                    BiError::StringError(format!("Failed to create {}: {}", dir.display(), e))
+                })?;
+                dir.join(format!(
+                    "revocations-{}.csv",
+                    chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+                ))
+            }
+        };
+        std::fs::write(&path, revocation_results_csv(results)).map_err(|e| {
+            BiError::StringError(format!("Failed to write {}: {}", path.display(), e))
+        })?;
+        println!("Wrote the results to {}", path.display());
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct FindDuplicates {
+    /// Output format
+    #[arg(long, value_enum, default_value = "table")]
+    format: ReportFormat,
+}
+
+#[async_trait]
+impl Executable for FindDuplicates {
+    async fn execute(&self) -> Result<(), BiError> {
+        let identities = IdentitiesService::new()
+            .build()
+            .await
+            .list_identities(None, None)
+            .await?
+            .identities;
+        let clusters = find_duplicate_clusters(&identities);
+
+        match self.format {
+            ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&clusters)?),
+            ReportFormat::Csv => print!("{}", duplicate_rows_csv(&duplicate_rows(&clusters))),
+            ReportFormat::Table => {
+                if clusters.is_empty() {
+                    println!("No duplicate identities found.");
+                    return Ok(());
+                }
+                let mut table = Table::new(duplicate_rows(&clusters));
+                table.with(Style::extended());
+                println!("{}", table);
+                println!(
+                    "{} clusters of duplicates among {} identities. Merge them with `bi helper merge <keep-id> <duplicate-id>`.",
+                    clusters.len(),
+                    identities.len()
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct Merge {
+    /// ID of the identity to keep
+    keep_id: String,
+
+    /// ID of the duplicate identity whose memberships are moved
+    duplicate_id: String,
+
+    /// Delete the duplicate instead of suspending it
+    #[arg(long)]
+    delete: bool,
+
+    /// Merge without asking for confirmation
+    #[arg(long)]
+    yes: bool,
+}
+
+#[async_trait]
+impl Executable for Merge {
+    async fn execute(&self) -> Result<(), BiError> {
+        if self.keep_id == self.duplicate_id {
+            return Err(BiError::StringError(
+                "The identity to keep and the duplicate must differ".to_string(),
+            ));
+        }
+
+        let api_client = ApiClient::new(None, None).await;
+        let (tenant, realm) = api_client
+            .db
+            .get_default_tenant_and_realm()
+            .await?
+            .ok_or_else(|| BiError::StringError("No default tenant/realm set".to_string()))?;
+
+        let identities_service = IdentitiesService::new().build().await;
+        let keep = identities_service.get_identity(&self.keep_id).await?;
+        let duplicate = identities_service.get_identity(&self.duplicate_id).await?;
+
+        let describe = |identity: &Identity| {
+            format!(
+                "{} ({}, {})",
+                identity.id,
+                identity.traits.username,
+                identity
+                    .traits
+                    .primary_email_address
+                    .as_deref()
+                    .unwrap_or("<no email provided>")
+            )
+        };
+        println!("Keep {}", describe(&keep));
+        println!(
+            "{} {}",
+            if self.delete { "Delete" } else { "Suspend" },
+            describe(&duplicate)
+        );
+
+        if !self.yes {
+            print!("Merge them? [y/N]: ");
+            io::stdout().flush().unwrap();
+            let mut input = String::new();
+            io::stdin().read_line(&mut input).unwrap();
+            if !input.trim().eq_ignore_ascii_case("y") {
+                println!("Nothing was merged.");
+                return Ok(());
+            }
+        }
+
+        let receipt =
+            merge_identities(&api_client, &tenant, &realm, &keep, &duplicate, self.delete).await?;
+        for (kind, names) in [
+            ("groups", &receipt.groups),
+            ("roles", &receipt.roles),
+            ("SSO configs", &receipt.sso_configs),
+        ] {
+            if !names.is_empty() {
+                println!("Moved {}: {}", kind, names.join(", "));
+            }
+        }
+        if !receipt.failures.is_empty() {
+            for failure in &receipt.failures {
+                println!("  ! {}", failure);
+            }
+            return Err(BiError::StringError(format!(
+                "Not every membership could be moved, so {} was left as it is",
+                duplicate.id
+            )));
+        }
+        println!(
+            "{} {}.",
+            if self.delete { "Deleted" } else { "Suspended" },
+            duplicate.id
+        );
+        Ok(())
+    }
+}
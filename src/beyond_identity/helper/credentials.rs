@@ -0,0 +1,415 @@
+use crate::beyond_identity::api::common::service::CredentialsService;
+use crate::beyond_identity::api::credentials::api::CredentialsApi;
+use crate::beyond_identity::api::credentials::types::Credential;
+use crate::beyond_identity::api::identities::types::{Identity, Status};
+use crate::common::csv;
+use crate::common::error::BiError;
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use clap::ValueEnum;
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::path::Path;
+use tabled::Tabled;
+
+// Number of identities whose credentials are fetched at the same time
+const FETCH_CONCURRENCY: usize = 8;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+/// Something worth reviewing about a credential or its identity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialFlag {
+    /// The identity holds more than one active credential
+    MultipleActive,
+    /// The credential is active and older than the stale threshold
+    Stale,
+    /// Every credential of the identity is revoked
+    OnlyRevoked,
+    /// The identity is suspended but still holds active credentials
+    SuspendedWithActive,
+}
+
+impl CredentialFlag {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CredentialFlag::MultipleActive => "multiple_active",
+            CredentialFlag::Stale => "stale",
+            CredentialFlag::OnlyRevoked => "only_revoked",
+            CredentialFlag::SuspendedWithActive => "suspended_with_active",
+        }
+    }
+}
+
+/// A credential joined with the identity that holds it.
+#[derive(Debug, Serialize, Tabled)]
+pub struct CredentialReportRow {
+    #[tabled(rename = "Identity ID")]
+    pub identity_id: String,
+    #[tabled(rename = "Email")]
+    pub email: String,
+    #[tabled(rename = "Display Name")]
+    pub display_name: String,
+    #[tabled(rename = "Identity Status")]
+    pub identity_status: String,
+    #[tabled(rename = "Credential ID")]
+    pub credential_id: String,
+    #[tabled(rename = "State")]
+    pub state: String,
+    #[tabled(rename = "CSR Type")]
+    pub csr_type: String,
+    #[tabled(rename = "JWK Thumbprint")]
+    pub jwk_thumbprint: String,
+    #[tabled(rename = "Created")]
+    pub create_time: String,
+    #[tabled(rename = "Age (days)", display_with = "display_age")]
+    pub age_days: Option<i64>,
+    #[tabled(rename = "Flags", display_with = "display_flags")]
+    pub flags: Vec<CredentialFlag>,
+}
+
+fn display_age(age_days: &Option<i64>) -> String {
+    age_days.map(|days| days.to_string()).unwrap_or_default()
+}
+
+fn display_flags(flags: &[CredentialFlag]) -> String {
+    flags
+        .iter()
+        .map(CredentialFlag::name)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn is_active(credential: &Credential) -> bool {
+    credential.state == "ACTIVE"
+}
+
+// Age of a credential in whole days, or None when its creation time can't be parsed
+pub fn credential_age_days(credential: &Credential, now: DateTime<Utc>) -> Option<i64> {
+    DateTime::parse_from_rfc3339(&credential.create_time)
+        .ok()
+        .map(|created| (now - created.with_timezone(&Utc)).num_days())
+}
+
+// Fetches the credentials of every identity, a few identities at a time. The
+// result keeps the order of `identity_ids`, and a failed lookup only fails the
+// entry of its identity.
+pub async fn fetch_credentials(identity_ids: &[String]) -> Vec<Result<Vec<Credential>, BiError>> {
+    let credentials_service = CredentialsService::new().build().await;
+    let credentials_service = &credentials_service;
+    stream::iter(identity_ids.to_vec())
+        .map(|identity_id| async move {
+            Ok(credentials_service
+                .list_credentials(&identity_id, None, None)
+                .await?
+                .credentials)
+        })
+        .buffered(FETCH_CONCURRENCY)
+        .collect()
+        .await
+}
+
+pub async fn fetch_identity_credentials(
+    identities: Vec<Identity>,
+) -> Vec<(Identity, Result<Vec<Credential>, BiError>)> {
+    let identity_ids: Vec<String> = identities.iter().map(|i| i.id.clone()).collect();
+    let credentials = fetch_credentials(&identity_ids).await;
+    identities.into_iter().zip(credentials).collect()
+}
+
+// Builds one row per credential and flags what needs review. Active credentials
+// created more than `stale_after_days` days before `now` are flagged as stale.
+pub fn build_credentials_report(
+    identity_credentials: &[(Identity, Vec<Credential>)],
+    stale_after_days: i64,
+    now: DateTime<Utc>,
+) -> Vec<CredentialReportRow> {
+    let mut rows = Vec::new();
+    for (identity, credentials) in identity_credentials {
+        let active = credentials.iter().filter(|c| is_active(c)).count();
+        let only_revoked = !credentials.is_empty() && active == 0;
+        let suspended = matches!(identity.status, Some(Status::Suspended));
+
+        for credential in credentials {
+            let age_days = credential_age_days(credential, now);
+            let mut flags = Vec::new();
+            if is_active(credential) {
+                if active > 1 {
+                    flags.push(CredentialFlag::MultipleActive);
+                }
+                if age_days.is_some_and(|days| days > stale_after_days) {
+                    flags.push(CredentialFlag::Stale);
+                }
+                if suspended {
+                    flags.push(CredentialFlag::SuspendedWithActive);
+                }
+            }
+            if only_revoked {
+                flags.push(CredentialFlag::OnlyRevoked);
+            }
+
+            rows.push(CredentialReportRow {
+                identity_id: identity.id.clone(),
+                email: identity
+                    .traits
+                    .primary_email_address
+                    .clone()
+                    .unwrap_or_default(),
+                display_name: identity.display_name.clone(),
+                identity_status: match identity.status {
+                    Some(Status::Suspended) => "suspended".to_string(),
+                    Some(Status::Active) => "active".to_string(),
+                    None => String::new(),
+                },
+                credential_id: credential.id.clone(),
+                state: credential.state.clone(),
+                csr_type: credential.csr_type.clone(),
+                jwk_thumbprint: credential.jwk_thumbprint.clone(),
+                create_time: credential.create_time.clone(),
+                age_days,
+                flags,
+            });
+        }
+    }
+    rows
+}
+
+pub fn credentials_report_csv(rows: &[CredentialReportRow]) -> String {
+    let mut output = csv::format_record(&[
+        "identity_id",
+        "email",
+        "display_name",
+        "identity_status",
+        "credential_id",
+        "state",
+        "csr_type",
+        "jwk_thumbprint",
+        "create_time",
+        "age_days",
+        "flags",
+    ]);
+    for row in rows {
+        output.push_str(&csv::format_record(&[
+            row.identity_id.clone(),
+            row.email.clone(),
+            row.display_name.clone(),
+            row.identity_status.clone(),
+            row.credential_id.clone(),
+            row.state.clone(),
+            row.csr_type.clone(),
+            row.jwk_thumbprint.clone(),
+            row.create_time.clone(),
+            display_age(&row.age_days),
+            display_flags(&row.flags),
+        ]));
+    }
+    output
+}
+
+// Parses a `--created-before` value, either a date (midnight UTC) or an RFC 3339 timestamp
+pub fn parse_created_before(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(NaiveTime::MIN).and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|d| d.with_timezone(&Utc))
+        .map_err(|_| {
+            format!(
+                "\"{}\" is neither a YYYY-MM-DD date nor an RFC 3339 timestamp",
+                value
+            )
+        })
+}
+
+/// A line of a `revoke-credentials --file`: an identity, and optionally the
+/// only one of its credentials to revoke.
+#[derive(Clone, Debug)]
+pub struct RevocationTarget {
+    pub identity_id: String,
+    pub credential_id: Option<String>,
+}
+
+/// Revocation targets read from a file.
+#[derive(Debug)]
+pub struct RevocationTargets {
+    pub targets: Vec<RevocationTarget>,
+    /// Rows skipped because their identity ID is blank
+    pub skipped: usize,
+}
+
+// Reads revocation targets from a CSV file. With a header row naming an
+// `identity_id` column (and optionally a `credential_id` column), as written by
+// `credentials-report --format csv`, those columns are used. Otherwise the first
+// column holds identity IDs and an optional second column credential IDs.
+pub fn read_revocation_targets(path: &Path) -> Result<RevocationTargets, BiError> {
+    Ok(revocation_targets(csv::read(path)?))
+}
+
+fn revocation_targets(mut records: Vec<Vec<String>>) -> RevocationTargets {
+    let header = records.first().cloned().unwrap_or_default();
+    let (identity_column, credential_column) = match header.iter().position(|f| f == "identity_id")
+    {
+        Some(identity_column) => {
+            records.remove(0);
+            (
+                identity_column,
+                header.iter().position(|f| f == "credential_id"),
+            )
+        }
+        None => (0, Some(1)),
+    };
+
+    let mut targets = RevocationTargets {
+        targets: Vec::new(),
+        skipped: 0,
+    };
+    for record in records {
+        match record.get(identity_column).filter(|id| !id.is_empty()) {
+            Some(identity_id) => targets.targets.push(RevocationTarget {
+                identity_id: identity_id.clone(),
+                credential_id: credential_column
+                    .and_then(|column| record.get(column))
+                    .filter(|id| !id.is_empty())
+                    .cloned(),
+            }),
+            None => targets.skipped += 1,
+        }
+    }
+    targets
+}
+
+/// The outcome of revoking one credential, or of listing the credentials of an
+/// identity when that failed, in which case `credential_id` is empty.
+#[derive(Debug, Serialize)]
+pub struct RevocationResult {
+    pub identity_id: String,
+    pub credential_id: String,
+    pub error: Option<String>,
+}
+
+// Revokes the credentials, at most `concurrency` at a time. Failures are
+// reported in the results instead of stopping the other revocations.
+pub async fn revoke_credentials(
+    credentials: &[Credential],
+    concurrency: usize,
+) -> Vec<RevocationResult> {
+    let credentials_service = CredentialsService::new().build().await;
+    let credentials_service = &credentials_service;
+    stream::iter(credentials.to_vec())
+        .map(|credential| async move {
+            let error = credentials_service
+                .revoke_credential(&credential.id, &credential.identity_id)
+                .await
+                .err()
+                .map(|err| err.to_string());
+            match &error {
+                None => println!("Revoked credential {}", credential.id),
+                Some(err) => println!("Failed to revoke credential {}: {}", credential.id, err),
+            }
+            RevocationResult {
+                identity_id: credential.identity_id.clone(),
+                credential_id: credential.id.clone(),
+                error,
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+pub fn revocation_results_csv(results: &[RevocationResult]) -> String {
+    let mut output = csv::format_record(&["identity_id", "credential_id", "result", "error"]);
+    for result in results {
+        output.push_str(&csv::format_record(&[
+            result.identity_id.as_str(),
+            result.credential_id.as_str(),
+            if result.error.is_none() {
+                "revoked"
+            } else {
+                "failed"
+            },
+            result.error.as_deref().unwrap_or_default(),
+        ]));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets(text: &str) -> Vec<(String, Option<String>)> {
+        revocation_targets(csv::parse(text))
+            .targets
+            .into_iter()
+            .map(|target| (target.identity_id, target.credential_id))
+            .collect()
+    }
+
+    fn target(identity_id: &str, credential_id: Option<&str>) -> (String, Option<String>) {
+        (identity_id.to_string(), credential_id.map(str::to_string))
+    }
+
+    #[test]
+    fn uses_named_columns_of_a_header_row() {
+        let text = "email,credential_id,identity_id\n\
+                    \"doe, jane\",c1,i1\n\
+                    john@example.com,,i2\n";
+        assert_eq!(
+            targets(text),
+            vec![target("i1", Some("c1")), target("i2", None)]
+        );
+    }
+
+    #[test]
+    fn header_without_credential_column_revokes_every_credential() {
+        let text = "identity_id,email\ni1,jane@example.com\n";
+        assert_eq!(targets(text), vec![target("i1", None)]);
+    }
+
+    #[test]
+    fn reads_positional_columns_without_a_header() {
+        let text = "i1,c1\ni2\n,c3\ni4,\n";
+        assert_eq!(
+            targets(text),
+            vec![
+                target("i1", Some("c1")),
+                target("i2", None),
+                target("i4", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_rows_without_an_identity() {
+        let text = "identity_id,credential_id,email\ni1,c1,\n,c2,\n,,jane@example.com\n";
+        let targets = revocation_targets(csv::parse(text));
+        assert_eq!(targets.targets.len(), 1);
+        assert_eq!(targets.skipped, 2);
+    }
+
+    #[test]
+    fn reads_a_credentials_report() {
+        let row = CredentialReportRow {
+            identity_id: "i1".to_string(),
+            email: "jane@example.com".to_string(),
+            display_name: "Doe, Jane".to_string(),
+            identity_status: "active".to_string(),
+            credential_id: "c1".to_string(),
+            state: "ACTIVE".to_string(),
+            csr_type: "JWT".to_string(),
+            jwk_thumbprint: "thumbprint".to_string(),
+            create_time: "2024-01-01T00:00:00Z".to_string(),
+            age_days: Some(10),
+            flags: vec![CredentialFlag::MultipleActive, CredentialFlag::Stale],
+        };
+        let text = credentials_report_csv(&[row]);
+        assert_eq!(targets(&text), vec![target("i1", Some("c1"))]);
+    }
+}
@@ -0,0 +1,500 @@
+use super::resource_servers::fetch_beyond_identity_resource_servers;
+use super::roles::{change_role_membership, fetch_role_memberships};
+use super::sso_configs::{
+    change_sso_config_identities, fetch_sso_config_identity_ids, fetch_sso_configs,
+};
+
+use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::beyond_identity::api::common::service::{GroupsService, IdentitiesService};
+use crate::beyond_identity::api::groups::api::GroupsApi;
+use crate::beyond_identity::api::groups::types::{AddMembersRequest, DeleteMembersRequest};
+use crate::beyond_identity::api::identities::api::IdentitiesApi;
+use crate::beyond_identity::api::identities::types::{
+    Identity, PatchIdentity, PatchIdentityRequest, Status,
+};
+use crate::common::csv;
+use crate::common::database::models::{Realm, Tenant};
+use crate::common::error::BiError;
+
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use tabled::Tabled;
+
+/// Identities that share a normalized email address, username or external ID,
+/// directly or through other identities of the cluster.
+#[derive(Debug, Serialize)]
+pub struct DuplicateCluster {
+    /// The shared values, e.g. `email:jane@example.com`
+    pub matched_on: Vec<String>,
+    pub identities: Vec<Identity>,
+}
+
+// Keys an identity is matched on. Email addresses and usernames are compared
+// case-insensitively, and a username that is an email address also matches
+// that email address.
+fn duplicate_keys(identity: &Identity) -> Vec<String> {
+    let mut keys = Vec::new();
+    if let Some(email) = identity.traits.primary_email_address.as_deref() {
+        let email = email.trim().to_lowercase();
+        if !email.is_empty() {
+            keys.push(format!("email:{}", email));
+        }
+    }
+    let username = identity.traits.username.trim().to_lowercase();
+    if username.contains('@') {
+        keys.push(format!("email:{}", username));
+    } else if !username.is_empty() {
+        keys.push(format!("username:{}", username));
+    }
+    if let Some(external_id) = identity.traits.external_id.as_deref() {
+        let external_id = external_id.trim();
+        if !external_id.is_empty() {
+            keys.push(format!("external_id:{}", external_id));
+        }
+    }
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+// Groups identities that share any key into clusters, largest first. Identities
+// without duplicates are left out.
+pub fn find_duplicate_clusters(identities: &[Identity]) -> Vec<DuplicateCluster> {
+    let mut parents: Vec<usize> = (0..identities.len()).collect();
+    let mut key_owners: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, identity) in identities.iter().enumerate() {
+        for key in duplicate_keys(identity) {
+            key_owners.entry(key).or_default().push(i);
+        }
+    }
+    for owners in key_owners.values() {
+        for &other in &owners[1..] {
+            let (a, b) = (
+                find_root(&mut parents, owners[0]),
+                find_root(&mut parents, other),
+            );
+            parents[b] = a;
+        }
+    }
+
+    let mut clusters: HashMap<usize, (BTreeSet<String>, Vec<usize>)> = HashMap::new();
+    for i in 0..identities.len() {
+        let root = find_root(&mut parents, i);
+        clusters.entry(root).or_default().1.push(i);
+    }
+    for (key, owners) in &key_owners {
+        if owners.len() > 1 {
+            let root = find_root(&mut parents, owners[0]);
+            if let Some((matched_on, _)) = clusters.get_mut(&root) {
+                matched_on.insert(key.clone());
+            }
+        }
+    }
+
+    let mut clusters: Vec<DuplicateCluster> = clusters
+        .into_values()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(matched_on, members)| DuplicateCluster {
+            matched_on: matched_on.into_iter().collect(),
+            identities: members.into_iter().map(|i| identities[i].clone()).collect(),
+        })
+        .collect();
+    clusters.sort_by(|a, b| {
+        b.identities
+            .len()
+            .cmp(&a.identities.len())
+            .then_with(|| a.matched_on.cmp(&b.matched_on))
+    });
+    clusters
+}
+
+#[derive(Tabled)]
+pub struct DuplicateRow {
+    #[tabled(rename = "Cluster")]
+    pub cluster: usize,
+    #[tabled(rename = "Matched On")]
+    pub matched_on: String,
+    #[tabled(rename = "Identity ID")]
+    pub identity_id: String,
+    #[tabled(rename = "Username")]
+    pub username: String,
+    #[tabled(rename = "Email")]
+    pub email: String,
+    #[tabled(rename = "External ID")]
+    pub external_id: String,
+    #[tabled(rename = "Status")]
+    pub status: String,
+    #[tabled(rename = "Created")]
+    pub create_time: String,
+}
+
+// One row per identity, numbering clusters from 1
+pub fn duplicate_rows(clusters: &[DuplicateCluster]) -> Vec<DuplicateRow> {
+    clusters
+        .iter()
+        .enumerate()
+        .flat_map(|(i, cluster)| {
+            cluster.identities.iter().map(move |identity| DuplicateRow {
+                cluster: i + 1,
+                matched_on: cluster.matched_on.join(" "),
+                identity_id: identity.id.clone(),
+                username: identity.traits.username.clone(),
+                email: identity
+                    .traits
+                    .primary_email_address
+                    .clone()
+                    .unwrap_or_default(),
+                external_id: identity.traits.external_id.clone().unwrap_or_default(),
+                status: match identity.status {
+                    Some(Status::Suspended) => "suspended".to_string(),
+                    Some(Status::Active) => "active".to_string(),
+                    None => String::new(),
+                },
+                create_time: identity.create_time.clone(),
+            })
+        })
+        .collect()
+}
+
+pub fn duplicate_rows_csv(rows: &[DuplicateRow]) -> String {
+    let mut output = csv::format_record(&[
+        "cluster",
+        "matched_on",
+        "identity_id",
+        "username",
+        "email",
+        "external_id",
+        "status",
+        "create_time",
+    ]);
+    for row in rows {
+        output.push_str(&csv::format_record(&[
+            row.cluster.to_string(),
+            row.matched_on.clone(),
+            row.identity_id.clone(),
+            row.username.clone(),
+            row.email.clone(),
+            row.external_id.clone(),
+            row.status.clone(),
+            row.create_time.clone(),
+        ]));
+    }
+    output
+}
+
+/// What `merge_identities` moved onto the kept identity.
+#[derive(Debug, Default)]
+pub struct MergeReceipt {
+    pub groups: Vec<String>,
+    pub roles: Vec<String>,
+    pub sso_configs: Vec<String>,
+    /// Steps that failed, which have to be completed by hand
+    pub failures: Vec<String>,
+}
+
+// Moves the group, role and SSO config memberships of `duplicate` onto `keep`,
+// then deletes `duplicate` or, with `delete` unset, suspends it. The duplicate
+// is only deleted or suspended when every membership was moved, so nothing is
+// lost.
+pub async fn merge_identities(
+    api_client: &ApiClient,
+    tenant: &Tenant,
+    realm: &Realm,
+    keep: &Identity,
+    duplicate: &Identity,
+    delete: bool,
+) -> Result<MergeReceipt, BiError> {
+    let mut receipt = MergeReceipt::default();
+    let identities_service = IdentitiesService::new().build().await;
+    let groups_service = GroupsService::new().build().await;
+
+    // A membership that can't be listed can't be moved either, so listing
+    // failures are recorded like failed moves and keep the duplicate in place
+    let groups = match identities_service.list_groups(&duplicate.id, None).await {
+        Ok(groups) => groups.groups,
+        Err(err) => {
+            receipt
+                .failures
+                .push(format!("Failed to list the groups: {}", err));
+            Vec::new()
+        }
+    };
+    for group in groups {
+        let moved = async {
+            groups_service
+                .add_members(
+                    &group.id,
+                    &AddMembersRequest {
+                        identity_ids: vec![keep.id.clone()],
+                    },
+                )
+                .await?;
+            groups_service
+                .delete_members(
+                    &group.id,
+                    &DeleteMembersRequest {
+                        identity_ids: vec![duplicate.id.clone()],
+                    },
+                )
+                .await
+        }
+        .await;
+        match moved {
+            Ok(_) => receipt.groups.push(group.display_name),
+            Err(err) => receipt.failures.push(format!(
+                "Failed to move group {}: {}",
+                group.display_name, err
+            )),
+        }
+    }
+
+    let resource_servers = match fetch_beyond_identity_resource_servers(api_client).await {
+        Ok(resource_servers) => resource_servers,
+        Err(err) => {
+            receipt
+                .failures
+                .push(format!("Failed to list the resource servers: {}", err));
+            Vec::new()
+        }
+    };
+    for resource_server in resource_servers {
+        let roles =
+            match fetch_role_memberships(api_client, &duplicate.id, &resource_server.id).await {
+                Ok(roles) => roles,
+                Err(err) => {
+                    receipt.failures.push(format!(
+                        "Failed to list the roles of resource server {}: {}",
+                        resource_server.id, err
+                    ));
+                    continue;
+                }
+            };
+        for role in roles {
+            let moved = async {
+                change_role_membership(
+                    api_client,
+                    tenant,
+                    realm,
+                    &role.resource_server_id,
+                    &role.id,
+                    &keep.id,
+                    "addMembers",
+                )
+                .await?;
+                change_role_membership(
+                    api_client,
+                    tenant,
+                    realm,
+                    &role.resource_server_id,
+                    &role.id,
+                    &duplicate.id,
+                    "deleteMembers",
+                )
+                .await
+            }
+            .await;
+            match moved {
+                Ok(()) => receipt.roles.push(role.display_name),
+                Err(err) => receipt.failures.push(format!(
+                    "Failed to move role {}: {}",
+                    role.display_name, err
+                )),
+            }
+        }
+    }
+
+    let sso_configs = match fetch_sso_configs(api_client).await {
+        Ok(sso_configs) => sso_configs,
+        Err(err) => {
+            receipt
+                .failures
+                .push(format!("Failed to list the SSO configs: {}", err));
+            Vec::new()
+        }
+    };
+    for sso_config in sso_configs {
+        let identity_ids = match fetch_sso_config_identity_ids(api_client, &sso_config.id).await {
+            Ok(identity_ids) => identity_ids,
+            Err(err) => {
+                receipt.failures.push(format!(
+                    "Failed to list the identities of SSO config {}: {}",
+                    sso_config.display_name, err
+                ));
+                continue;
+            }
+        };
+        if !identity_ids.contains(&duplicate.id) {
+            continue;
+        }
+        let moved = async {
+            if !identity_ids.contains(&keep.id) {
+                change_sso_config_identities(
+                    api_client,
+                    &sso_config.id,
+                    std::slice::from_ref(&keep.id),
+                    "addIdentities",
+                )
+                .await?;
+            }
+            change_sso_config_identities(
+                api_client,
+                &sso_config.id,
+                std::slice::from_ref(&duplicate.id),
+                "removeIdentities",
+            )
+            .await
+        }
+        .await;
+        match moved {
+            Ok(()) => receipt.sso_configs.push(sso_config.display_name),
+            Err(err) => receipt.failures.push(format!(
+                "Failed to move SSO config {}: {}",
+                sso_config.display_name, err
+            )),
+        }
+    }
+
+    if !receipt.failures.is_empty() {
+        return Ok(receipt);
+    }
+    if delete {
+        identities_service.delete_identity(&duplicate.id).await?;
+    } else {
+        identities_service
+            .patch_identity(&PatchIdentityRequest {
+                identity: PatchIdentity {
+                    id: duplicate.id.clone(),
+                    display_name: None,
+                    status: Some(Status::Suspended),
+                    traits: None,
+                },
+            })
+            .await?;
+    }
+    Ok(receipt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beyond_identity::api::identities::types::{Traits, Type};
+
+    fn identity(
+        id: &str,
+        username: &str,
+        email: Option<&str>,
+        external_id: Option<&str>,
+    ) -> Identity {
+        Identity {
+            id: id.to_string(),
+            realm_id: "realm".to_string(),
+            tenant_id: "tenant".to_string(),
+            display_name: id.to_string(),
+            status: Some(Status::Active),
+            create_time: "2024-01-01T00:00:00Z".to_string(),
+            update_time: "2024-01-01T00:00:00Z".to_string(),
+            traits: Traits {
+                r#type: Type::TraitsV0,
+                username: username.to_string(),
+                primary_email_address: email.map(str::to_string),
+                external_id: external_id.map(str::to_string),
+                family_name: None,
+                given_name: None,
+            },
+        }
+    }
+
+    fn ids(cluster: &DuplicateCluster) -> Vec<&str> {
+        let mut ids: Vec<&str> = cluster.identities.iter().map(|i| i.id.as_str()).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn matches_email_case_insensitively() {
+        let clusters = find_duplicate_clusters(&[
+            identity("a", "jane", Some("Jane@Example.com"), None),
+            identity("b", "jdoe", Some(" jane@example.com "), None),
+            identity("c", "john", Some("john@example.com"), None),
+        ]);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(ids(&clusters[0]), vec!["a", "b"]);
+        assert_eq!(clusters[0].matched_on, vec!["email:jane@example.com"]);
+    }
+
+    #[test]
+    fn matches_username_and_external_id() {
+        let clusters = find_duplicate_clusters(&[
+            identity("a", "Jane", None, None),
+            identity("b", "jane", None, None),
+            identity("c", "john", None, Some("E1")),
+            identity("d", "jdoe", None, Some("E1")),
+            identity("e", "other", None, Some("e1")),
+        ]);
+        assert_eq!(clusters.len(), 2);
+        let mut found: Vec<(Vec<&str>, Vec<String>)> = clusters
+            .iter()
+            .map(|cluster| (ids(cluster), cluster.matched_on.clone()))
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                (vec!["a", "b"], vec!["username:jane".to_string()]),
+                (vec!["c", "d"], vec!["external_id:E1".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn username_that_is_an_email_matches_the_email() {
+        let clusters = find_duplicate_clusters(&[
+            identity("a", "Jane@example.com", None, None),
+            identity("b", "jdoe", Some("jane@example.com"), None),
+        ]);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(ids(&clusters[0]), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn clusters_transitively_and_largest_first() {
+        // a and b share an email, b and c an external ID, c and d a username
+        let clusters = find_duplicate_clusters(&[
+            identity("pair-1", "x", Some("x@example.com"), None),
+            identity("a", "alice", Some("alice@example.com"), None),
+            identity("b", "bob", Some("ALICE@example.com"), Some("E7")),
+            identity("c", "carol", None, Some("E7")),
+            identity("pair-2", "y", Some("x@example.com"), None),
+            identity("d", "Carol", None, None),
+            identity("single", "zed", Some("zed@example.com"), Some("E8")),
+        ]);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(ids(&clusters[0]), vec!["a", "b", "c", "d"]);
+        assert_eq!(
+            clusters[0].matched_on,
+            vec![
+                "email:alice@example.com",
+                "external_id:E7",
+                "username:carol",
+            ]
+        );
+        assert_eq!(ids(&clusters[1]), vec!["pair-1", "pair-2"]);
+    }
+
+    #[test]
+    fn ignores_blank_values() {
+        let clusters = find_duplicate_clusters(&[
+            identity("a", " ", Some(""), Some(" ")),
+            identity("b", "", Some(" "), Some("")),
+        ]);
+        assert!(clusters.is_empty());
+    }
+}
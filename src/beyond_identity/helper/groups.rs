@@ -1,4 +1,9 @@
 use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::beyond_identity::api::common::service::GroupsService;
+use crate::beyond_identity::api::groups::api::GroupsApi;
+use crate::beyond_identity::api::groups::types::{
+    AddMembersRequest, CreateGroup, CreateGroupRequest, DeleteMembersRequest, Group,
+};
 use crate::beyond_identity::api::identities::types::Identity;
 use crate::beyond_identity::helper::enrollment::get_credentials_for_identity;
 use crate::beyond_identity::helper::enrollment::Credential;
@@ -81,3 +86,71 @@ pub async fn get_unenrolled_identities_from_group(
 
     Ok(identities)
 }
+
+// Number of identity IDs sent in a single addMembers or deleteMembers call.
+pub const MEMBERSHIP_BATCH_SIZE: usize = 100;
+
+// Returns the group with the given display name from `existing_groups`, creating it
+// when no such group exists yet.
+pub async fn find_or_create_group(
+    groups_service: &GroupsService,
+    existing_groups: &[Group],
+    display_name: &str,
+    description: &str,
+) -> Result<Group, BiError> {
+    if let Some(group) = existing_groups
+        .iter()
+        .find(|group| group.display_name == display_name)
+    {
+        println!(
+            "Group {} already exists, reusing it for {}",
+            group.id, display_name
+        );
+        return Ok(group.clone());
+    }
+
+    groups_service
+        .create_group(&CreateGroupRequest {
+            group: CreateGroup {
+                display_name: display_name.to_string(),
+                description: description.to_string(),
+            },
+        })
+        .await
+}
+
+pub async fn add_members_in_batches(
+    groups_service: &GroupsService,
+    group_id: &str,
+    identity_ids: &[String],
+) -> Result<(), BiError> {
+    for batch in identity_ids.chunks(MEMBERSHIP_BATCH_SIZE) {
+        groups_service
+            .add_members(
+                group_id,
+                &AddMembersRequest {
+                    identity_ids: batch.to_vec(),
+                },
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn delete_members_in_batches(
+    groups_service: &GroupsService,
+    group_id: &str,
+    identity_ids: &[String],
+) -> Result<(), BiError> {
+    for batch in identity_ids.chunks(MEMBERSHIP_BATCH_SIZE) {
+        groups_service
+            .delete_members(
+                group_id,
+                &DeleteMembersRequest {
+                    identity_ids: batch.to_vec(),
+                },
+            )
+            .await?;
+    }
+    Ok(())
+}
@@ -1,203 +1,84 @@
-use super::enrollment::{get_credentials_for_identity, Credential};
-use super::resource_servers::fetch_beyond_identity_resource_servers;
-use super::roles::fetch_role_memberships;
-
-use crate::beyond_identity::api::common::api_client::ApiClient;
 use crate::beyond_identity::api::common::service::IdentitiesService;
 use crate::beyond_identity::api::identities::api::IdentitiesApi;
-use crate::beyond_identity::api::identities::types::Identity;
+use crate::beyond_identity::api::identities::types::{CreateIdentityRequest, Identity};
+use crate::common::database::Database;
 use crate::common::error::BiError;
 
-pub async fn delete_all_identities(api_client: &ApiClient) -> Result<(), BiError> {
-    let (tenant, realm) = match api_client.db.get_default_tenant_and_realm().await? {
-        Some((t, r)) => (t, r),
-        None => {
-            return Err(BiError::StringError(
-                "No default tenant/realm set".to_string(),
-            ))
-        }
-    };
-
-    let mut url = format!(
-        "{}/v1/tenants/{}/realms/{}/identities?page_size=200",
-        realm.api_base_url, tenant.id, realm.id
-    );
-
-    loop {
-        let response = api_client.client.get(&url).send().await?;
-
-        let status = response.status();
-        log::debug!("{} response status: {}", url, status);
-        if !status.is_success() {
-            let error_text = response.text().await?;
-            return Err(BiError::RequestError(status, error_text));
-        }
-
-        let response_text = response.text().await?;
-        log::debug!("{} response text: {}", url, response_text);
-        let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
-        let page_identities: Vec<Identity> =
-            serde_json::from_value(response_json["identities"].clone())?;
-
-        for identity in &page_identities {
-            IdentitiesService::new()
-                .build()
-                .await
-                .delete_identity(&identity.id)
-                .await
-                .expect("Failed to delete identity");
-            println!("Deleted identity {}", identity.id);
-        }
-
-        if let Some(next_page_token) = response_json
-            .get("next_page_token")
-            .and_then(|token| token.as_str())
-        {
-            url = format!(
-                "{}/v1/tenants/{}/realms/{}/identities?page_size=200&page_token={}",
-                realm.api_base_url, tenant.id, realm.id, next_page_token
-            );
-        } else {
-            break;
-        }
-    }
-
-    Ok(())
+use serde_json::json;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+// Writes `identities` to a timestamped JSON file in the snapshots directory
+// before they are deleted, so they can be inspected or recreated by hand.
+// Returns the path of the file.
+pub fn write_identities_snapshot(
+    tenant_id: &str,
+    realm_id: &str,
+    identities: &[Identity],
+) -> Result<PathBuf, BiError> {
+    let dir = Database::data_dir()?.join("snapshots");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| BiError::StringError(format!("Failed to create {}: {}", dir.display(), e)))?;
+
+    let now = chrono::Utc::now();
+    let path = dir.join(format!(
+        "identities-{}-{}-{}.json",
+        tenant_id,
+        realm_id,
+        now.format("%Y%m%dT%H%M%SZ")
+    ));
+    let snapshot = json!({
+        "tenant_id": tenant_id,
+        "realm_id": realm_id,
+        "created_at": now.to_rfc3339(),
+        "identities": identities,
+    });
+    std::fs::write(&path, serde_json::to_string_pretty(&snapshot)?)
+        .map_err(|e| BiError::StringError(format!("Failed to write {}: {}", path.display(), e)))?;
+    Ok(path)
 }
 
-pub async fn delete_unenrolled_identities(api_client: &ApiClient) -> Result<(), BiError> {
-    let (tenant, realm) = match api_client.db.get_default_tenant_and_realm().await? {
-        Some((t, r)) => (t, r),
-        None => {
-            return Err(BiError::StringError(
-                "No default tenant/realm set".to_string(),
-            ))
-        }
-    };
-
-    let mut url = format!(
-        "{}/v1/tenants/{}/realms/{}/identities?page_size=200",
-        realm.api_base_url, tenant.id, realm.id
-    );
-
-    loop {
-        let response = api_client.client.get(&url).send().await?;
-
-        let status = response.status();
-        log::debug!("{} response status: {}", url, status);
-        if !status.is_success() {
-            let error_text = response.text().await?;
-            return Err(BiError::RequestError(status, error_text));
+// Creates an identity for every request whose username and primary email are not
+// already used by one of `identities` (compared case-insensitively). Returns the
+// existing identities together with the newly created ones.
+pub async fn create_missing_identities(
+    identities_service: &IdentitiesService,
+    requests: &[CreateIdentityRequest],
+    mut identities: Vec<Identity>,
+) -> Result<Vec<Identity>, BiError> {
+    let mut usernames: HashSet<String> = identities
+        .iter()
+        .map(|identity| identity.traits.username.to_lowercase())
+        .collect();
+    let mut emails: HashSet<String> = identities
+        .iter()
+        .filter_map(|identity| identity.traits.primary_email_address.as_deref())
+        .map(|email| email.to_lowercase())
+        .collect();
+
+    for request in requests {
+        let traits = &request.identity.traits;
+        let username = traits.username.to_lowercase();
+        let email = traits
+            .primary_email_address
+            .as_deref()
+            .map(|email| email.to_lowercase());
+
+        if usernames.contains(&username) || email.as_ref().is_some_and(|e| emails.contains(e)) {
+            log::debug!("Identity for {} already exists", traits.username);
+            continue;
         }
 
-        let response_text = response.text().await?;
-        log::debug!("{} response text: {}", url, response_text);
-        let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
-        let page_identities: Vec<Identity> =
-            serde_json::from_value(response_json["identities"].clone())?;
-
-        for identity in &page_identities {
-            let credentials = get_credentials_for_identity(api_client, &identity.id)
-                .await
-                .expect("Failed to fetch credentials");
-            let enrolled = credentials
-                .into_iter()
-                .filter(|cred| cred.realm_id == realm.id && cred.tenant_id == tenant.id)
-                .collect::<Vec<Credential>>();
-            if enrolled.is_empty() {
-                IdentitiesService::new()
-                    .build()
-                    .await
-                    .delete_identity(&identity.id)
-                    .await
-                    .expect("Failed to delete identity");
-                println!("Deleted identity {}", identity.id);
+        match identities_service.create_identity(request).await {
+            Ok(identity) => {
+                println!("Created identity {} for {}", identity.id, traits.username);
+                usernames.insert(username);
+                emails.extend(email);
+                identities.push(identity);
             }
-        }
-
-        if let Some(next_page_token) = response_json
-            .get("next_page_token")
-            .and_then(|token| token.as_str())
-        {
-            url = format!(
-                "{}/v1/tenants/{}/realms/{}/identities?page_size=200&page_token={}",
-                realm.api_base_url, tenant.id, realm.id, next_page_token
-            );
-        } else {
-            break;
-        }
-    }
-
-    Ok(())
-}
-
-pub async fn delete_norole_identities(api_client: &ApiClient) -> Result<(), BiError> {
-    let (tenant, realm) = match api_client.db.get_default_tenant_and_realm().await? {
-        Some((t, r)) => (t, r),
-        None => {
-            return Err(BiError::StringError(
-                "No default tenant/realm set".to_string(),
-            ))
-        }
-    };
-
-    let mut url = format!(
-        "{}/v1/tenants/{}/realms/{}/identities?page_size=200",
-        realm.api_base_url, tenant.id, realm.id
-    );
-
-    let resource_servers = fetch_beyond_identity_resource_servers(api_client)
-        .await
-        .expect("Failed to fetch resource servers");
-
-    loop {
-        let response = api_client.client.get(&url).send().await?;
-
-        let status = response.status();
-        log::debug!("{} response status: {}", url, status);
-        if !status.is_success() {
-            let error_text = response.text().await?;
-            return Err(BiError::RequestError(status, error_text));
-        }
-
-        let response_text = response.text().await?;
-        log::debug!("{} response text: {}", url, response_text);
-        let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
-        let page_identities: Vec<Identity> =
-            serde_json::from_value(response_json["identities"].clone())?;
-
-        for identity in &page_identities {
-            let mut has_role = false;
-            for resource_server in &resource_servers {
-                let roles =
-                    fetch_role_memberships(api_client, &identity.id, &resource_server.id).await?;
-
-                has_role |= !roles.is_empty();
-            }
-
-            if !has_role {
-                IdentitiesService::new()
-                    .build()
-                    .await
-                    .delete_identity(&identity.id)
-                    .await
-                    .expect("Failed to delete identity");
-                println!("Deleted identity {}", identity.id);
-            }
-        }
-
-        if let Some(next_page_token) = response_json
-            .get("next_page_token")
-            .and_then(|token| token.as_str())
-        {
-            url = format!(
-                "{}/v1/tenants/{}/realms/{}/identities?page_size=200&page_token={}",
-                realm.api_base_url, tenant.id, realm.id, next_page_token
-            );
-        } else {
-            break;
+            Err(err) => println!("Failed to create identity for {}: {}", traits.username, err),
         }
     }
 
-    Ok(())
+    Ok(identities)
 }
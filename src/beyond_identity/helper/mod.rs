@@ -1,8 +1,11 @@
 pub mod admin;
 pub mod command;
+pub mod credentials;
+pub mod duplicates;
 pub mod enrollment;
 pub mod groups;
 pub mod identities;
+pub mod offboard;
 pub mod resource_servers;
 pub mod roles;
 pub mod sso_configs;
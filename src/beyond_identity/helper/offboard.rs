@@ -0,0 +1,351 @@
+use super::resource_servers::fetch_beyond_identity_resource_servers;
+use super::roles::{change_role_membership, fetch_role_memberships};
+
+use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::beyond_identity::api::common::filter::Filter;
+use crate::beyond_identity::api::common::service::{
+    CredentialsService, GroupsService, IdentitiesService,
+};
+use crate::beyond_identity::api::credentials::api::CredentialsApi;
+use crate::beyond_identity::api::groups::api::GroupsApi;
+use crate::beyond_identity::api::groups::types::{AddMembersRequest, DeleteMembersRequest};
+use crate::beyond_identity::api::identities::api::IdentitiesApi;
+use crate::beyond_identity::api::identities::types::{
+    Identity, PatchIdentity, PatchIdentityRequest, Status,
+};
+use crate::common::database::models::{Offboarding, Realm, Tenant};
+use crate::common::error::BiError;
+
+use serde::{Deserialize, Serialize};
+
+/// Everything `offboard_identity` did, kept so it can be reversed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OffboardReceipt {
+    pub identity_id: String,
+    pub display_name: String,
+    pub username: String,
+    pub email: Option<String>,
+    /// Status before the identity was suspended
+    pub previous_status: Option<Status>,
+    pub suspended: bool,
+    pub revoked_credentials: Vec<String>,
+    pub removed_groups: Vec<RemovedGroup>,
+    pub removed_roles: Vec<RemovedRole>,
+    /// Steps that failed, which have to be completed by hand
+    pub failures: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RemovedGroup {
+    pub id: String,
+    pub display_name: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RemovedRole {
+    pub resource_server_id: String,
+    pub id: String,
+    pub display_name: String,
+}
+
+// Finds an identity by ID, or by primary email address or username when
+// the target contains an `@`.
+pub async fn resolve_identity(target: &str) -> Result<Identity, BiError> {
+    let identities_service = IdentitiesService::new().build().await;
+    if !target.contains('@') {
+        return identities_service.get_identity(target).await;
+    }
+
+    let escaped = target.replace('\\', "\\\\").replace('"', "\\\"");
+    let filter = Filter::new(Some(format!(
+        "traits.primary_email_address eq \"{0}\" or traits.username eq \"{0}\"",
+        escaped
+    )))?;
+    let mut identities = identities_service
+        .list_identities(filter, None)
+        .await?
+        .identities;
+    match identities.len() {
+        0 => Err(BiError::StringError(format!(
+            "No identity found for {}",
+            target
+        ))),
+        1 => Ok(identities.remove(0)),
+        n => Err(BiError::StringError(format!(
+            "{} identities match {}. Offboard them by ID instead.",
+            n, target
+        ))),
+    }
+}
+
+// Suspends the identity, revokes its credentials and removes it from its
+// groups and roles. Failing steps are noted in the receipt instead of
+// stopping the offboarding, so as much access as possible is removed.
+pub async fn offboard_identity(
+    api_client: &ApiClient,
+    tenant: &Tenant,
+    realm: &Realm,
+    identity: &Identity,
+) -> Result<OffboardReceipt, BiError> {
+    let mut receipt = OffboardReceipt {
+        identity_id: identity.id.clone(),
+        display_name: identity.display_name.clone(),
+        username: identity.traits.username.clone(),
+        email: identity.traits.primary_email_address.clone(),
+        previous_status: identity.status.clone(),
+        suspended: false,
+        revoked_credentials: Vec::new(),
+        removed_groups: Vec::new(),
+        removed_roles: Vec::new(),
+        failures: Vec::new(),
+    };
+
+    let request = PatchIdentityRequest {
+        identity: PatchIdentity {
+            id: identity.id.clone(),
+            display_name: None,
+            status: Some(Status::Suspended),
+            traits: None,
+        },
+    };
+    match IdentitiesService::new()
+        .build()
+        .await
+        .patch_identity(&request)
+        .await
+    {
+        Ok(_) => receipt.suspended = true,
+        Err(err) => receipt
+            .failures
+            .push(format!("Failed to suspend the identity: {}", err)),
+    }
+
+    let credentials_service = CredentialsService::new().build().await;
+    match credentials_service
+        .list_credentials(&identity.id, None, None)
+        .await
+    {
+        Ok(credentials) => {
+            for credential in credentials
+                .credentials
+                .iter()
+                .filter(|c| c.state != "REVOKED")
+            {
+                match credentials_service
+                    .revoke_credential(&credential.id, &identity.id)
+                    .await
+                {
+                    Ok(_) => receipt.revoked_credentials.push(credential.id.clone()),
+                    Err(err) => receipt.failures.push(format!(
+                        "Failed to revoke credential {}: {}",
+                        credential.id, err
+                    )),
+                }
+            }
+        }
+        Err(err) => receipt
+            .failures
+            .push(format!("Failed to list credentials: {}", err)),
+    }
+
+    let groups_service = GroupsService::new().build().await;
+    match IdentitiesService::new()
+        .build()
+        .await
+        .list_groups(&identity.id, None)
+        .await
+    {
+        Ok(groups) => {
+            for group in groups.groups {
+                let request = DeleteMembersRequest {
+                    identity_ids: vec![identity.id.clone()],
+                };
+                match groups_service.delete_members(&group.id, &request).await {
+                    Ok(_) => receipt.removed_groups.push(RemovedGroup {
+                        id: group.id,
+                        display_name: group.display_name,
+                    }),
+                    Err(err) => receipt.failures.push(format!(
+                        "Failed to remove from group {}: {}",
+                        group.display_name, err
+                    )),
+                }
+            }
+        }
+        Err(err) => receipt
+            .failures
+            .push(format!("Failed to list groups: {}", err)),
+    }
+
+    match fetch_beyond_identity_resource_servers(api_client).await {
+        Ok(resource_servers) => {
+            for resource_server in resource_servers {
+                let roles =
+                    match fetch_role_memberships(api_client, &identity.id, &resource_server.id)
+                        .await
+                    {
+                        Ok(roles) => roles,
+                        Err(err) => {
+                            receipt.failures.push(format!(
+                                "Failed to list roles of resource server {}: {}",
+                                resource_server.display_name, err
+                            ));
+                            continue;
+                        }
+                    };
+                for role in roles {
+                    match change_role_membership(
+                        api_client,
+                        tenant,
+                        realm,
+                        &role.resource_server_id,
+                        &role.id,
+                        &identity.id,
+                        "deleteMembers",
+                    )
+                    .await
+                    {
+                        Ok(()) => receipt.removed_roles.push(RemovedRole {
+                            resource_server_id: role.resource_server_id,
+                            id: role.id,
+                            display_name: role.display_name,
+                        }),
+                        Err(err) => receipt.failures.push(format!(
+                            "Failed to remove from role {}: {}",
+                            role.display_name, err
+                        )),
+                    }
+                }
+            }
+        }
+        Err(err) => receipt
+            .failures
+            .push(format!("Failed to list resource servers: {}", err)),
+    }
+
+    Ok(receipt)
+}
+
+// Reverses an offboarding as far as possible: restores the previous status
+// and the group and role memberships. Returns what could not be restored.
+pub async fn reinstate_identity(
+    api_client: &ApiClient,
+    tenant: &Tenant,
+    realm: &Realm,
+    offboarding: &Offboarding,
+) -> Result<Vec<String>, BiError> {
+    let receipt: OffboardReceipt = serde_json::from_str(&offboarding.receipt)?;
+    let mut failures = Vec::new();
+
+    if receipt.suspended {
+        let request = PatchIdentityRequest {
+            identity: PatchIdentity {
+                id: receipt.identity_id.clone(),
+                display_name: None,
+                status: Some(receipt.previous_status.clone().unwrap_or(Status::Active)),
+                traits: None,
+            },
+        };
+        match IdentitiesService::new()
+            .build()
+            .await
+            .patch_identity(&request)
+            .await
+        {
+            Ok(_) => println!("Restored the status of {}", receipt.identity_id),
+            Err(err) => failures.push(format!("Failed to restore the status: {}", err)),
+        }
+    }
+
+    let groups_service = GroupsService::new().build().await;
+    for group in &receipt.removed_groups {
+        let request = AddMembersRequest {
+            identity_ids: vec![receipt.identity_id.clone()],
+        };
+        match groups_service.add_members(&group.id, &request).await {
+            Ok(_) => println!("Added back to group {}", group.display_name),
+            Err(err) => failures.push(format!(
+                "Failed to add back to group {}: {}",
+                group.display_name, err
+            )),
+        }
+    }
+
+    for role in &receipt.removed_roles {
+        match change_role_membership(
+            api_client,
+            tenant,
+            realm,
+            &role.resource_server_id,
+            &role.id,
+            &receipt.identity_id,
+            "addMembers",
+        )
+        .await
+        {
+            Ok(()) => println!("Added back to role {}", role.display_name),
+            Err(err) => failures.push(format!(
+                "Failed to add back to role {}: {}",
+                role.display_name, err
+            )),
+        }
+    }
+
+    if !receipt.revoked_credentials.is_empty() {
+        failures.push(format!(
+            "{} revoked credential(s) can't be reinstated. The identity has to enroll again, e.g. with `helper send-enrollment-email`.",
+            receipt.revoked_credentials.len()
+        ));
+    }
+
+    Ok(failures)
+}
+
+pub fn print_receipt(receipt: &OffboardReceipt, delete_after: Option<i64>) {
+    println!(
+        "Offboarded {} ({})",
+        receipt.email.as_deref().unwrap_or(&receipt.username),
+        receipt.identity_id
+    );
+    println!(
+        "  Suspended:           {}",
+        if receipt.suspended { "yes" } else { "no" }
+    );
+    println!(
+        "  Revoked credentials: {}",
+        list_or_none(receipt.revoked_credentials.iter().cloned())
+    );
+    println!(
+        "  Removed from groups: {}",
+        list_or_none(
+            receipt
+                .removed_groups
+                .iter()
+                .map(|g| g.display_name.clone())
+        )
+    );
+    println!(
+        "  Removed from roles:  {}",
+        list_or_none(receipt.removed_roles.iter().map(|r| r.display_name.clone()))
+    );
+    if let Some(delete_after) = delete_after {
+        println!(
+            "  Deleted after:       {}",
+            chrono::DateTime::from_timestamp(delete_after, 0)
+                .map(|d| d.to_rfc3339())
+                .unwrap_or_else(|| delete_after.to_string())
+        );
+    }
+    for failure in &receipt.failures {
+        println!("  ! {}", failure);
+    }
+}
+
+fn list_or_none(items: impl Iterator<Item = String>) -> String {
+    let items: Vec<String> = items.collect();
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items.join(", ")
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     beyond_identity::api::{common::api_client::ApiClient, roles::types::Role},
+    common::database::models::{Realm, Tenant},
     common::error::BiError,
 };
 
@@ -107,3 +108,38 @@ pub async fn fetch_beyond_identity_roles(
 
     Ok(roles)
 }
+
+// Adds or removes an identity as a direct member of a role
+pub async fn change_role_membership(
+    api_client: &ApiClient,
+    tenant: &Tenant,
+    realm: &Realm,
+    resource_server_id: &str,
+    role_id: &str,
+    identity_id: &str,
+    method: &str,
+) -> Result<(), BiError> {
+    let url = format!(
+        "{}/v1/tenants/{}/realms/{}/resource-servers/{}/roles/{}:{}",
+        realm.api_base_url, tenant.id, realm.id, resource_server_id, role_id, method,
+    );
+
+    let response = api_client
+        .client
+        .post(&url)
+        .json(&serde_json::json!({
+            "group_ids": [],
+            "identity_ids": [identity_id]
+        }))
+        .send()
+        .await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        log::debug!("{} response status: {}", url, status);
+        let error_text = response.text().await?;
+        return Err(BiError::RequestError(status, error_text));
+    }
+
+    Ok(())
+}
@@ -5,6 +5,10 @@ use crate::common::error::BiError;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::HashSet;
+use std::path::Path;
+use tabled::settings::style::Style;
+use tabled::{Table, Tabled};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DeleteSsoConfig {
@@ -95,7 +99,7 @@ pub async fn create_sso_config(
     Ok(sso_config)
 }
 
-fn sanitize_label(label: &str) -> String {
+pub fn sanitize_label(label: &str) -> String {
     let re = Regex::new(r"[^a-zA-Z\s]").unwrap();
     let sanitized_label: String = re.replace_all(label, "").to_string();
     let trimmed_label = sanitized_label.trim();
@@ -157,3 +161,224 @@ pub async fn assign_identities_to_sso_config(
 
     Ok(())
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SsoConfigSummary {
+    pub id: String,
+    pub display_name: String,
+}
+
+pub async fn fetch_sso_configs(api_client: &ApiClient) -> Result<Vec<SsoConfigSummary>, BiError> {
+    let (tenant, realm) = match api_client.db.get_default_tenant_and_realm().await? {
+        Some((t, r)) => (t, r),
+        None => {
+            return Err(BiError::StringError(
+                "No default tenant/realm set".to_string(),
+            ))
+        }
+    };
+
+    let mut sso_configs = Vec::new();
+    let base_url = format!(
+        "{}/v1/tenants/{}/realms/{}/sso-configs?page_size=100",
+        realm.api_base_url, tenant.id, realm.id
+    );
+    let mut url = base_url.clone();
+
+    loop {
+        let response = api_client.client.get(&url).send().await?;
+
+        let status = response.status();
+        log::debug!("{} response status: {}", url, status);
+        if !status.is_success() {
+            let error_text = response.text().await?;
+            return Err(BiError::RequestError(status, error_text));
+        }
+
+        let response_text = response.text().await?;
+        log::debug!("{} response text: {}", url, response_text);
+        let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
+        let page_sso_configs: Vec<SsoConfigSummary> =
+            serde_json::from_value(response_json["sso_configs"].clone())?;
+
+        sso_configs.extend(page_sso_configs);
+
+        if let Some(next_page_token) = response_json
+            .get("next_page_token")
+            .and_then(|token| token.as_str())
+        {
+            url = format!("{}&page_token={}", base_url, next_page_token);
+        } else {
+            break;
+        }
+    }
+
+    Ok(sso_configs)
+}
+
+// Returns the IDs of the identities directly assigned to an SSO config
+pub async fn fetch_sso_config_identity_ids(
+    api_client: &ApiClient,
+    sso_config_id: &str,
+) -> Result<Vec<String>, BiError> {
+    let (tenant, realm) = match api_client.db.get_default_tenant_and_realm().await? {
+        Some((t, r)) => (t, r),
+        None => {
+            return Err(BiError::StringError(
+                "No default tenant/realm set".to_string(),
+            ))
+        }
+    };
+
+    let mut identity_ids = Vec::new();
+    let base_url = format!(
+        "{}/v1/tenants/{}/realms/{}/sso-configs/{}:listIdentities?page_size=100",
+        realm.api_base_url, tenant.id, realm.id, sso_config_id
+    );
+    let mut url = base_url.clone();
+
+    loop {
+        let response = api_client.client.get(&url).send().await?;
+
+        let status = response.status();
+        log::debug!("{} response status: {}", url, status);
+        if !status.is_success() {
+            let error_text = response.text().await?;
+            return Err(BiError::RequestError(status, error_text));
+        }
+
+        let response_text = response.text().await?;
+        log::debug!("{} response text: {}", url, response_text);
+        let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
+        let page_identities: Vec<Identity> =
+            serde_json::from_value(response_json["identities"].clone())?;
+
+        identity_ids.extend(page_identities.into_iter().map(|identity| identity.id));
+
+        if let Some(next_page_token) = response_json
+            .get("next_page_token")
+            .and_then(|token| token.as_str())
+        {
+            url = format!("{}&page_token={}", base_url, next_page_token);
+        } else {
+            break;
+        }
+    }
+
+    Ok(identity_ids)
+}
+
+// Assigns identities to or unassigns them from an SSO config. `method` is
+// `addIdentities` or `removeIdentities`.
+pub async fn change_sso_config_identities(
+    api_client: &ApiClient,
+    sso_config_id: &str,
+    identity_ids: &[String],
+    method: &str,
+) -> Result<(), BiError> {
+    let (tenant, realm) = match api_client.db.get_default_tenant_and_realm().await? {
+        Some((t, r)) => (t, r),
+        None => {
+            return Err(BiError::StringError(
+                "No default tenant/realm set".to_string(),
+            ))
+        }
+    };
+
+    let url = format!(
+        "{}/v1/tenants/{}/realms/{}/sso-configs/{}:{}",
+        realm.api_base_url, tenant.id, realm.id, sso_config_id, method
+    );
+
+    let response = api_client
+        .client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(&json!({ "identity_ids": identity_ids }))
+        .send()
+        .await?;
+
+    let status = response.status();
+    let response_text = response.text().await?;
+
+    log::debug!(
+        "{} response status: {} and text: {}",
+        url,
+        status,
+        response_text
+    );
+
+    if !status.is_success() {
+        return Err(BiError::RequestError(status, response_text));
+    }
+
+    Ok(())
+}
+
+/// Describes what a fast migration would create for a single source application.
+#[derive(Debug, Serialize, Tabled)]
+pub struct SsoConfigPlan {
+    #[tabled(rename = "Source Application")]
+    pub source_application: String,
+    #[tabled(rename = "SSO Config Label")]
+    pub label: String,
+    #[tabled(rename = "Login Link")]
+    pub login_link: String,
+    #[tabled(rename = "Icon")]
+    pub icon: String,
+    #[tabled(rename = "Users To Assign")]
+    pub users_to_assign: usize,
+    #[tabled(rename = "Unmatched Source Users")]
+    pub unmatched_source_users: usize,
+}
+
+// Builds the plan for one source application. `source_user_emails` holds the email of
+// every user assigned in the source (None when the source has no email for the user)
+// and `identities_to_assign` the Beyond Identity identities those emails matched.
+pub fn plan_sso_config(
+    source_application: &str,
+    login_link: String,
+    icon_url: Option<String>,
+    source_user_emails: &[Option<&str>],
+    identities_to_assign: &[Identity],
+) -> SsoConfigPlan {
+    let matched_emails: HashSet<&str> = identities_to_assign
+        .iter()
+        .filter_map(|identity| identity.traits.primary_email_address.as_deref())
+        .collect();
+    let unmatched_source_users = source_user_emails
+        .iter()
+        .filter(|email| !email.is_some_and(|email| matched_emails.contains(email)))
+        .count();
+
+    SsoConfigPlan {
+        source_application: source_application.to_string(),
+        label: sanitize_label(source_application),
+        login_link,
+        icon: icon_url.unwrap_or_default(),
+        users_to_assign: identities_to_assign.len(),
+        unmatched_source_users,
+    }
+}
+
+// Prints the plans as a table followed by their JSON form. When `report_path` is set
+// the JSON is written to that file instead of stdout.
+pub fn print_sso_config_plans(
+    plans: &[SsoConfigPlan],
+    report_path: Option<&Path>,
+) -> Result<(), BiError> {
+    let mut table = Table::new(plans);
+    table.with(Style::modern());
+    println!("{}", table);
+
+    let json = serde_json::to_string_pretty(plans)?;
+    match report_path {
+        Some(path) => {
+            std::fs::write(path, json).map_err(|e| BiError::StringError(e.to_string()))?;
+            println!("Dry run report written to {}", path.display());
+        }
+        None => println!("{}", json),
+    }
+
+    Ok(())
+}
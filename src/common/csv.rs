@@ -0,0 +1,110 @@
+use crate::common::error::BiError;
+
+use std::path::Path;
+
+// Quotes a field when it contains a separator, quote or line break
+pub fn escape_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Formats one record, including the trailing line break
+pub fn format_record<S: AsRef<str>>(fields: &[S]) -> String {
+    let mut line = fields
+        .iter()
+        .map(|field| escape_field(field.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push('\n');
+    line
+}
+
+// Splits RFC 4180 text into records. Quoted fields may contain separators,
+// doubled quotes and line breaks. Fields are trimmed and blank lines skipped.
+pub fn parse(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => record.push(std::mem::take(&mut field).trim().to_string()),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field).trim().to_string());
+                let record = std::mem::take(&mut record);
+                if record.iter().any(|f| !f.is_empty()) {
+                    records.push(record);
+                }
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field.trim().to_string());
+        if record.iter().any(|f| !f.is_empty()) {
+            records.push(record);
+        }
+    }
+    records
+}
+
+pub fn read(path: &Path) -> Result<Vec<Vec<String>>, BiError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| BiError::StringError(format!("Failed to read {}: {}", path.display(), e)))?;
+    Ok(parse(&text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quoted_fields() {
+        let records = parse("id,name\n1,\"Doe, Jane\"\n2,\"say \"\"hi\"\"\"\n");
+        assert_eq!(
+            records,
+            vec![
+                vec!["id", "name"],
+                vec!["1", "Doe, Jane"],
+                vec!["2", "say \"hi\""],
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_line_breaks_inside_quotes() {
+        let records = parse("1,\"first\nsecond\"\r\n2,third");
+        assert_eq!(
+            records,
+            vec![vec!["1", "first\nsecond"], vec!["2", "third"]]
+        );
+    }
+
+    #[test]
+    fn trims_fields_and_skips_blank_records() {
+        let records = parse("\n a , b \r\n\r\n,\n c,d\n\n");
+        assert_eq!(records, vec![vec!["a", "b"], vec!["c", "d"]]);
+    }
+
+    #[test]
+    fn parses_what_it_formats() {
+        let fields = ["plain", "with,comma", "with \"quotes\"", "two\nlines", ""];
+        let text = format_record(&fields);
+        assert_eq!(
+            text,
+            "plain,\"with,comma\",\"with \"\"quotes\"\"\",\"two\nlines\",\n"
+        );
+        assert_eq!(parse(&text), vec![fields.to_vec()]);
+    }
+}
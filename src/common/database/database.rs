@@ -1,31 +1,75 @@
 use super::models::{
-    AiProvider, AnthropicConfig, OktaConfig, OneloginConfig, OpenaiConfig, Realm, Tenant, Token,
+    AiProvider, AnthropicConfig, AuditEntry, EntraConfig, GoogleConfig, HttpConfig, Offboarding,
+    OktaConfig, OneloginConfig, OpenaiConfig, Profile, Realm, SecretsBackend, Tenant, Token,
 };
 
 use crate::common::error::BiError;
+use crate::common::secrets::{self, realm_secret_key, setting_secret_key, SecretStore};
 
 use directories::ProjectDirs;
 use log::debug;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use sqlx::{
     migrate::{MigrateDatabase, Migrator},
     query, query_as,
     sqlite::SqlitePool,
     Row, Sqlite,
 };
+use std::path::PathBuf;
+use std::sync::{Arc, Once, OnceLock};
 
 #[derive(Clone)]
 pub struct Database {
     pool: SqlitePool,
+    secrets: Arc<dyn SecretStore>,
+    profile: String,
 }
 
+/// Profile that always exists and is used when no other profile is selected.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Tag of realms that refuse bulk deletes unless explicitly overridden.
+pub const PROTECTED_TAG: &str = "protected";
+
+// Profile selected with --profile or BI_PROFILE for this process
+static PROFILE_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+static PLAINTEXT_SECRETS_WARNED: Once = Once::new();
+
 static MIGRATOR: Migrator = sqlx::migrate!();
 
 const OKTA_CONFIG_KEY: &str = "okta_config";
 const ONELOGIN_CONFIG_KEY: &str = "onelogin_config";
+const ENTRA_CONFIG_KEY: &str = "entra_config";
+const GOOGLE_CONFIG_KEY: &str = "google_config";
 const OPENAI_CONFIG_KEY: &str = "openai_config";
 const ANTHROPIC_CONFIG_KEY: &str = "anthropic_config";
 const DEFAULT_AI_PROVIDER_KEY: &str = "default_ai_provider";
+const SECRETS_BACKEND_KEY: &str = "secrets_backend";
+const ACTIVE_PROFILE_KEY: &str = "active_profile";
+const HTTP_CONFIG_KEY: &str = "http_config";
+
+// Settings shared by all profiles. Everything else is scoped to the active profile.
+const GLOBAL_SETTINGS_KEYS: &[&str] = &[
+    SECRETS_BACKEND_KEY,
+    ACTIVE_PROFILE_KEY,
+    HTTP_CONFIG_KEY,
+    OPENAI_CONFIG_KEY,
+    ANTHROPIC_CONFIG_KEY,
+    DEFAULT_AI_PROVIDER_KEY,
+];
+
+// Fields of settings entries that hold secrets. These are kept in the secret
+// store instead of the settings table.
+const SECRET_SETTINGS_FIELDS: &[(&str, &[&str])] = &[
+    (OKTA_CONFIG_KEY, &["api_key"]),
+    (ONELOGIN_CONFIG_KEY, &["client_secret"]),
+    (ENTRA_CONFIG_KEY, &["client_secret"]),
+    (GOOGLE_CONFIG_KEY, &["service_account_key"]),
+    (OPENAI_CONFIG_KEY, &["api_key"]),
+    (ANTHROPIC_CONFIG_KEY, &["api_key"]),
+];
 
 impl Database {
     // Initialize the database, create if not exists, and run migrations
@@ -55,23 +99,213 @@ impl Database {
             debug!("Detected migration: {:?}", migration);
         }
 
+        Self::restrict_permissions()?;
+
+        let backend = Self::initialize_secrets_backend(&pool).await?;
+        debug!("Using secrets backend: {:?}", backend);
+        if backend == SecretsBackend::Database {
+            PLAINTEXT_SECRETS_WARNED.call_once(|| {
+                eprintln!(
+                    "Warning: secrets are stored in plaintext in the local database. \
+                     Run `bi config secrets migrate --backend keyring`, or \
+                     `--backend encrypted-file` where no OS keyring is available, \
+                     to encrypt them."
+                )
+            });
+        }
+        let secrets = secrets::open(&backend, &pool)?;
+
+        let profile = match PROFILE_OVERRIDE.get() {
+            Some(profile) => profile.clone(),
+            None => Self::load_global_setting::<String>(&pool, ACTIVE_PROFILE_KEY)
+                .await?
+                .unwrap_or_else(|| DEFAULT_PROFILE.to_string()),
+        };
+        let exists = query_as::<_, (i64,)>("SELECT COUNT(*) FROM profiles WHERE name = ?")
+            .bind(&profile)
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?
+            .0
+            > 0;
+        if !exists {
+            return Err(BiError::StringError(format!(
+                "Profile '{}' does not exist",
+                profile
+            )));
+        }
+        debug!("Using profile: {}", profile);
+
         debug!("Database and migrations initialized successfully.");
-        Ok(Database { pool })
+        Ok(Database {
+            pool,
+            secrets,
+            profile,
+        })
     }
 
-    // db_url creates and returns url of a database in a user writable
-    // directory that is meant for storing application specific data
-    fn db_url() -> Result<String, BiError> {
+    // Select the profile used by every database opened in this process,
+    // taking precedence over the active profile.
+    pub fn set_profile_override(profile: &str) {
+        _ = PROFILE_OVERRIDE.set(profile.to_string());
+    }
+
+    // data_dir creates and returns a user writable directory that is meant
+    // for storing application specific data
+    pub fn data_dir() -> Result<PathBuf, BiError> {
         let proj_dirs = ProjectDirs::from("com", "BeyondIdentity", env!("CARGO_PKG_NAME")).ok_or(
             BiError::StringError("Failed to determine project directory".to_string()),
         )?;
-        let db_dir = proj_dirs.data_local_dir();
-        std::fs::create_dir_all(db_dir).map_err(|e| BiError::StringError(e.to_string()))?;
-        let db_path = db_dir.join("sqlite.db");
+        let data_dir = proj_dirs.data_local_dir();
+        std::fs::create_dir_all(data_dir).map_err(|e| BiError::StringError(e.to_string()))?;
+        Ok(data_dir.to_path_buf())
+    }
+
+    // db_url returns url of the database in the data directory
+    fn db_url() -> Result<String, BiError> {
+        let db_path = Self::data_dir()?.join("sqlite.db");
         let db_url = format!("sqlite://{}", db_path.display());
         Ok(db_url)
     }
 
+    // Make sure the database file is only readable by the current user
+    #[cfg(unix)]
+    fn restrict_permissions() -> Result<(), BiError> {
+        use std::os::unix::fs::PermissionsExt;
+        let db_path = Self::data_dir()?.join("sqlite.db");
+        std::fs::set_permissions(db_path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| BiError::StringError(e.to_string()))
+    }
+
+    #[cfg(not(unix))]
+    fn restrict_permissions() -> Result<(), BiError> {
+        Ok(())
+    }
+
+    // Read a global setting straight from the settings table. Used while the
+    // database is being initialized, so it must not touch the secret store.
+    async fn load_global_setting<T: for<'de> Deserialize<'de>>(
+        pool: &SqlitePool,
+        key: &str,
+    ) -> Result<Option<T>, BiError> {
+        let value =
+            query_as::<_, (String,)>("SELECT value FROM settings WHERE profile = '' AND key = ?")
+                .bind(key)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| BiError::StringError(e.to_string()))?;
+        match value {
+            Some((value,)) => Ok(Some(serde_json::from_str(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn load_secrets_backend(pool: &SqlitePool) -> Result<SecretsBackend, BiError> {
+        Ok(Self::load_global_setting(pool, SECRETS_BACKEND_KEY)
+            .await?
+            .unwrap_or_default())
+    }
+
+    // Pick the secrets backend the first time the database is opened. Installs
+    // that already keep secrets in the database stay on it until migrated,
+    // everything else starts on the OS keyring when one is available.
+    async fn initialize_secrets_backend(pool: &SqlitePool) -> Result<SecretsBackend, BiError> {
+        if let Some(backend) = Self::load_global_setting(pool, SECRETS_BACKEND_KEY).await? {
+            return Ok(backend);
+        }
+        let stored_secrets = query_as::<_, (i64,)>("SELECT COUNT(*) FROM secrets")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?
+            .0;
+        let backend = if stored_secrets > 0 {
+            SecretsBackend::Database
+        } else {
+            secrets::default_backend().await?
+        };
+        query("INSERT OR REPLACE INTO settings (profile, key, value) VALUES ('', ?, ?)")
+            .bind(SECRETS_BACKEND_KEY)
+            .bind(serde_json::to_string(&backend)?)
+            .execute(pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+        Ok(backend)
+    }
+
+    // Get the configured secrets backend
+    pub async fn get_secrets_backend(&self) -> Result<SecretsBackend, BiError> {
+        Self::load_secrets_backend(&self.pool).await
+    }
+
+    // Move every known secret from the current backend into the given one and
+    // make it the active backend. Returns the number of secrets moved.
+    pub async fn migrate_secrets(&self, backend: SecretsBackend) -> Result<usize, BiError> {
+        if self.get_secrets_backend().await? == backend {
+            return Err(BiError::StringError(
+                "Secrets are already stored in this backend".to_string(),
+            ));
+        }
+        let target = secrets::open(&backend, &self.pool)?;
+
+        let mut keys: Vec<String> =
+            query_as::<_, (String, String)>("SELECT tenant_id, id FROM realms")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| BiError::StringError(e.to_string()))?
+                .iter()
+                .map(|(tenant_id, realm_id)| realm_secret_key(tenant_id, realm_id))
+                .collect();
+        let settings = query_as::<_, (String, String)>("SELECT profile, key FROM settings")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+        for (profile, setting) in settings {
+            keys.extend(
+                Self::secret_fields(&setting)
+                    .iter()
+                    .map(|field| setting_secret_key(&profile, &setting, field)),
+            );
+        }
+
+        // Copy everything before touching the source so a failure part way
+        // through leaves the current backend intact.
+        let mut moved = Vec::new();
+        for key in keys {
+            if let Some(value) = self.secrets.get(&key).await? {
+                target.set(&key, &value).await?;
+                moved.push(key);
+            }
+        }
+
+        self.set_config(SECRETS_BACKEND_KEY, &backend).await?;
+
+        for key in &moved {
+            self.secrets.delete(key).await?;
+        }
+
+        // Make sure deleted plaintext does not linger in free database pages.
+        query("VACUUM")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+
+        Ok(moved.len())
+    }
+
+    // Fill in the client secret of a realm from the secret store
+    async fn resolve_realm_secret(&self, mut realm: Realm) -> Result<Realm, BiError> {
+        if realm.client_secret.is_empty() {
+            if let Some(secret) = self
+                .secrets
+                .get(&realm_secret_key(&realm.tenant_id, &realm.id))
+                .await?
+            {
+                realm.client_secret = secret;
+            }
+        }
+        Ok(realm)
+    }
+
     // Get all tenants with their corresponding realms
     pub async fn get_all_tenants_with_realms(&self) -> Result<Vec<(Tenant, Vec<Realm>)>, BiError> {
         // Fetch all tenants
@@ -83,12 +317,17 @@ impl Database {
         // For each tenant, fetch associated realms and construct TenantWithRealms
         let mut tenants_with_realms = Vec::new();
         for tenant in tenants {
-            let realms: Vec<Realm> = query_as("SELECT * FROM realms WHERE tenant_id = ?")
+            let rows: Vec<Realm> = query_as("SELECT * FROM realms WHERE tenant_id = ?")
                 .bind(&tenant.id)
                 .fetch_all(&self.pool)
                 .await
                 .map_err(|e| BiError::StringError(e.to_string()))?;
 
+            let mut realms = Vec::new();
+            for realm in rows {
+                realms.push(self.resolve_realm_secret(realm).await?);
+            }
+
             tenants_with_realms.push((tenant, realms));
         }
         Ok(tenants_with_realms)
@@ -103,13 +342,21 @@ impl Database {
             .await
             .map_err(|e| BiError::StringError(e.to_string()))?;
 
+        // Store the client secret separately from the realm
+        self.secrets
+            .set(
+                &realm_secret_key(&realm.tenant_id, &realm.id),
+                &realm.client_secret,
+            )
+            .await?;
+
         // Insert or replace the realm
         query("INSERT OR REPLACE INTO realms (id, tenant_id, application_id, client_id, client_secret, open_id_configuration_url, auth_base_url, api_base_url) VALUES (?, ?, ?, ?, ?, ?, ?, ?)")
                 .bind(&realm.id)
                 .bind(&realm.tenant_id)
                 .bind(&realm.application_id)
                 .bind(&realm.client_id)
-                .bind(&realm.client_secret)
+                .bind("")
                 .bind(&realm.open_id_configuration_url)
                 .bind(&realm.auth_base_url)
                 .bind(&realm.api_base_url)
@@ -121,23 +368,12 @@ impl Database {
     }
 
     // Delete a tenant/realm pair, removing the tenant if it has no other realms.
-    // Also unsets the default if the tenant/realm pair being deleted is set as the default.
+    // Also unsets the tenant/realm pair in every profile that uses it as its default.
     pub async fn delete_tenant_realm_pair(
         &self,
         tenant_id: &str,
         realm_id: &str,
     ) -> Result<(), BiError> {
-        // Check if this tenant/realm pair is set as the default
-        let is_default = query_as::<_, (i64,)>(
-            "SELECT COUNT(*) FROM defaults WHERE tenant_id = ? AND realm_id = ?",
-        )
-        .bind(tenant_id)
-        .bind(realm_id)
-        .fetch_one(&self.pool)
-        .await
-        .map_err(|e| BiError::StringError(e.to_string()))?
-        .0 > 0;
-
         // First, delete the specific realm
         query("DELETE FROM realms WHERE tenant_id = ? AND id = ?")
             .bind(tenant_id)
@@ -146,6 +382,17 @@ impl Database {
             .await
             .map_err(|e| BiError::StringError(e.to_string()))?;
 
+        self.secrets
+            .delete(&realm_secret_key(tenant_id, realm_id))
+            .await?;
+
+        query("DELETE FROM realm_tags WHERE tenant_id = ? AND realm_id = ?")
+            .bind(tenant_id)
+            .bind(realm_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+
         // Check if any realms remain for this tenant
         let remaining_realms_count: i64 =
             query_as::<_, (i64,)>("SELECT COUNT(*) FROM realms WHERE tenant_id = ?")
@@ -164,25 +411,25 @@ impl Database {
                 .map_err(|e| BiError::StringError(e.to_string()))?;
         }
 
-        // If this tenant/realm was set as the default, unset it
-        if is_default {
-            query("DELETE FROM defaults WHERE tenant_id = ? AND realm_id = ?")
-                .bind(tenant_id)
-                .bind(realm_id)
-                .execute(&self.pool)
-                .await
-                .map_err(|e| BiError::StringError(e.to_string()))?;
-        }
+        // Unset this tenant/realm in every profile that uses it
+        query("UPDATE profiles SET tenant_id = NULL, realm_id = NULL WHERE tenant_id = ? AND realm_id = ?")
+            .bind(tenant_id)
+            .bind(realm_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
 
         Ok(())
     }
 
-    // Get default tenant and realm
+    // Get default tenant and realm of the active profile
     pub async fn get_default_tenant_and_realm(&self) -> Result<Option<(Tenant, Realm)>, BiError> {
         // Fetch the default tenant and realm IDs
-        if let Some(defaults) =
-            query_as::<_, (String, String)>("SELECT tenant_id, realm_id FROM defaults WHERE id = 1")
-                .fetch_optional(&self.pool)
+        if let Some(defaults) = query_as::<_, (String, String)>(
+            "SELECT tenant_id, realm_id FROM profiles WHERE name = ? AND tenant_id IS NOT NULL AND realm_id IS NOT NULL",
+        )
+        .bind(&self.profile)
+        .fetch_optional(&self.pool)
                 .await
                 .map_err(|e| BiError::StringError(e.to_string()))?
         {
@@ -201,22 +448,22 @@ impl Database {
                 .await
                 .map_err(|e| BiError::StringError(e.to_string()))?;
 
-            Ok(Some((tenant, realm)))
+            Ok(Some((tenant, self.resolve_realm_secret(realm).await?)))
         } else {
             Ok(None)
         }
     }
 
-    // Set default tenant and realm. There can only be one set at a time.
+    // Set default tenant and realm of the active profile. There can only be one set at a time.
     pub async fn set_default_tenant_and_realm(
         &self,
         tenant_id: &str,
         realm_id: &str,
     ) -> Result<(), BiError> {
-        // Insert or replace the default tenant and realm
-        query("INSERT OR REPLACE INTO defaults (id, tenant_id, realm_id) VALUES (1, ?, ?)")
+        query("UPDATE profiles SET tenant_id = ?, realm_id = ? WHERE name = ?")
             .bind(tenant_id)
             .bind(realm_id)
+            .bind(&self.profile)
             .execute(&self.pool)
             .await
             .map_err(|e| BiError::StringError(e.to_string()))?;
@@ -258,6 +505,24 @@ impl Database {
         Ok(())
     }
 
+    // Get all cached tokens
+    pub async fn get_all_tokens(&self) -> Result<Vec<Token>, BiError> {
+        query_as::<_, Token>("SELECT * FROM tokens ORDER BY tenant_id, realm_id")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))
+    }
+
+    // Delete all cached tokens
+    pub async fn delete_all_tokens(&self) -> Result<u64, BiError> {
+        let result = query("DELETE FROM tokens")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
     // Delete a token by tenant_id and realm_id
     pub async fn delete_token(&self, tenant_id: &str, realm_id: &str) -> Result<(), BiError> {
         query("DELETE FROM tokens WHERE tenant_id = ? AND realm_id = ?")
@@ -270,6 +535,310 @@ impl Database {
         Ok(())
     }
 
+    // Record a mutating API request and return the ID of the new entry
+    pub async fn add_audit_entry(&self, entry: &AuditEntry) -> Result<i64, BiError> {
+        let result = query(
+            "INSERT INTO audit_log (timestamp, os_user, command_line, method, url, body_hash, status, resource_id, pre_image, request_body)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(entry.timestamp)
+        .bind(&entry.os_user)
+        .bind(&entry.command_line)
+        .bind(&entry.method)
+        .bind(&entry.url)
+        .bind(&entry.body_hash)
+        .bind(entry.status)
+        .bind(&entry.resource_id)
+        .bind(&entry.pre_image)
+        .bind(&entry.request_body)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| BiError::StringError(e.to_string()))?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    // Get recorded API requests, most recent first
+    pub async fn get_audit_entries(&self, limit: Option<i64>) -> Result<Vec<AuditEntry>, BiError> {
+        query_as::<_, AuditEntry>("SELECT * FROM audit_log ORDER BY id DESC LIMIT ?")
+            .bind(limit.unwrap_or(-1))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))
+    }
+
+    // Get a recorded API request by ID
+    pub async fn get_audit_entry(&self, id: i64) -> Result<Option<AuditEntry>, BiError> {
+        query_as::<_, AuditEntry>("SELECT * FROM audit_log WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))
+    }
+
+    // Mark a recorded action as undone
+    pub async fn set_audit_entry_undone(&self, id: i64) -> Result<(), BiError> {
+        query("UPDATE audit_log SET undone_at = ? WHERE id = ?")
+            .bind(chrono::Utc::now().timestamp())
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // Remember that a resource was recreated under a new ID
+    pub async fn set_recreated_id(&self, old_id: &str, new_id: &str) -> Result<(), BiError> {
+        query("INSERT OR REPLACE INTO recreated_ids (old_id, new_id) VALUES (?, ?)")
+            .bind(old_id)
+            .bind(new_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // Current ID of a resource, following any recreations
+    pub async fn resolve_recreated_id(&self, id: &str) -> Result<String, BiError> {
+        let mut current = id.to_string();
+        // Bounded in case of a cycle
+        for _ in 0..100 {
+            let next: Option<String> =
+                sqlx::query_scalar("SELECT new_id FROM recreated_ids WHERE old_id = ?")
+                    .bind(&current)
+                    .fetch_optional(&self.pool)
+                    .await
+                    .map_err(|e| BiError::StringError(e.to_string()))?;
+            match next {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        Ok(current)
+    }
+
+    // Record an offboarding, returning its ID
+    pub async fn add_offboarding(&self, offboarding: &Offboarding) -> Result<i64, BiError> {
+        let result = query(
+            "INSERT INTO offboardings (tenant_id, realm_id, identity_id, offboarded_at, receipt, delete_after)
+            VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&offboarding.tenant_id)
+        .bind(&offboarding.realm_id)
+        .bind(&offboarding.identity_id)
+        .bind(offboarding.offboarded_at)
+        .bind(&offboarding.receipt)
+        .bind(offboarding.delete_after)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| BiError::StringError(e.to_string()))?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    // Get the offboardings of an identity that were neither reversed nor
+    // completed, most recent first
+    pub async fn get_open_offboardings(
+        &self,
+        tenant_id: &str,
+        realm_id: &str,
+        identity_id: &str,
+    ) -> Result<Vec<Offboarding>, BiError> {
+        query_as::<_, Offboarding>(
+            "SELECT * FROM offboardings
+            WHERE tenant_id = ? AND realm_id = ? AND identity_id = ?
+            AND deleted_at IS NULL AND reinstated_at IS NULL
+            ORDER BY id DESC",
+        )
+        .bind(tenant_id)
+        .bind(realm_id)
+        .bind(identity_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| BiError::StringError(e.to_string()))
+    }
+
+    // Get the open offboardings of a realm whose grace period ended by `now`
+    pub async fn get_due_offboardings(
+        &self,
+        tenant_id: &str,
+        realm_id: &str,
+        now: i64,
+    ) -> Result<Vec<Offboarding>, BiError> {
+        query_as::<_, Offboarding>(
+            "SELECT * FROM offboardings
+            WHERE tenant_id = ? AND realm_id = ? AND delete_after <= ?
+            AND deleted_at IS NULL AND reinstated_at IS NULL
+            ORDER BY id",
+        )
+        .bind(tenant_id)
+        .bind(realm_id)
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| BiError::StringError(e.to_string()))
+    }
+
+    // Mark the identity of an offboarding as deleted
+    pub async fn set_offboarding_deleted(&self, id: i64) -> Result<(), BiError> {
+        query("UPDATE offboardings SET deleted_at = ? WHERE id = ?")
+            .bind(chrono::Utc::now().timestamp())
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // Mark an offboarding as reversed
+    pub async fn set_offboarding_reinstated(&self, id: i64) -> Result<(), BiError> {
+        query("UPDATE offboardings SET reinstated_at = ? WHERE id = ?")
+            .bind(chrono::Utc::now().timestamp())
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // Tag a configured realm
+    pub async fn add_realm_tag(
+        &self,
+        tenant_id: &str,
+        realm_id: &str,
+        tag: &str,
+    ) -> Result<(), BiError> {
+        query("INSERT OR IGNORE INTO realm_tags (tenant_id, realm_id, tag) VALUES (?, ?, ?)")
+            .bind(tenant_id)
+            .bind(realm_id)
+            .bind(tag)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // Remove a tag from a configured realm
+    pub async fn remove_realm_tag(
+        &self,
+        tenant_id: &str,
+        realm_id: &str,
+        tag: &str,
+    ) -> Result<(), BiError> {
+        query("DELETE FROM realm_tags WHERE tenant_id = ? AND realm_id = ? AND tag = ?")
+            .bind(tenant_id)
+            .bind(realm_id)
+            .bind(tag)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // Get the tags of a configured realm
+    pub async fn get_realm_tags(
+        &self,
+        tenant_id: &str,
+        realm_id: &str,
+    ) -> Result<Vec<String>, BiError> {
+        sqlx::query_scalar(
+            "SELECT tag FROM realm_tags WHERE tenant_id = ? AND realm_id = ? ORDER BY tag",
+        )
+        .bind(tenant_id)
+        .bind(realm_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| BiError::StringError(e.to_string()))
+    }
+
+    // Name of the profile this database was opened with
+    pub fn profile(&self) -> &str {
+        &self.profile
+    }
+
+    // Get all profiles
+    pub async fn get_profiles(&self) -> Result<Vec<Profile>, BiError> {
+        query_as::<_, Profile>("SELECT * FROM profiles ORDER BY name")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))
+    }
+
+    // Get the keys of all settings stored for a profile
+    pub async fn get_profile_settings_keys(&self, name: &str) -> Result<Vec<String>, BiError> {
+        Ok(
+            query_as::<_, (String,)>("SELECT key FROM settings WHERE profile = ? ORDER BY key")
+                .bind(name)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| BiError::StringError(e.to_string()))?
+                .into_iter()
+                .map(|(key,)| key)
+                .collect(),
+        )
+    }
+
+    // Create a new profile, optionally bound to an already configured tenant/realm
+    pub async fn create_profile(
+        &self,
+        name: &str,
+        tenant_id: Option<&str>,
+        realm_id: Option<&str>,
+    ) -> Result<(), BiError> {
+        query("INSERT INTO profiles (name, tenant_id, realm_id) VALUES (?, ?, ?)")
+            .bind(name)
+            .bind(tenant_id)
+            .bind(realm_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+        Ok(())
+    }
+
+    // Delete a profile along with its settings and their secrets
+    pub async fn delete_profile(&self, name: &str) -> Result<(), BiError> {
+        for setting in self.get_profile_settings_keys(name).await? {
+            for field in Self::secret_fields(&setting) {
+                self.secrets
+                    .delete(&setting_secret_key(name, &setting, field))
+                    .await?;
+            }
+        }
+
+        query("DELETE FROM settings WHERE profile = ?")
+            .bind(name)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+
+        query("DELETE FROM profiles WHERE name = ?")
+            .bind(name)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // Get the profile used when neither --profile nor BI_PROFILE is given
+    pub async fn get_active_profile(&self) -> Result<String, BiError> {
+        Ok(self
+            .get_config(ACTIVE_PROFILE_KEY)
+            .await?
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string()))
+    }
+
+    // Set the profile used when neither --profile nor BI_PROFILE is given
+    pub async fn set_active_profile(&self, name: &str) -> Result<(), BiError> {
+        self.set_config(ACTIVE_PROFILE_KEY, &name).await
+    }
+
     // Get okta config from db
     pub async fn get_okta_config(&self) -> Result<Option<OktaConfig>, BiError> {
         self.get_config(OKTA_CONFIG_KEY).await
@@ -290,6 +859,26 @@ impl Database {
         self.set_config(ONELOGIN_CONFIG_KEY, &config).await
     }
 
+    // Get entra config from db
+    pub async fn get_entra_config(&self) -> Result<Option<EntraConfig>, BiError> {
+        self.get_config(ENTRA_CONFIG_KEY).await
+    }
+
+    // Set entra config in db
+    pub async fn set_entra_config(&self, config: EntraConfig) -> Result<(), BiError> {
+        self.set_config(ENTRA_CONFIG_KEY, &config).await
+    }
+
+    // Get google config from db
+    pub async fn get_google_config(&self) -> Result<Option<GoogleConfig>, BiError> {
+        self.get_config(GOOGLE_CONFIG_KEY).await
+    }
+
+    // Set google config in db
+    pub async fn set_google_config(&self, config: GoogleConfig) -> Result<(), BiError> {
+        self.set_config(GOOGLE_CONFIG_KEY, &config).await
+    }
+
     // Get openai config from db
     pub async fn get_openai_config(&self) -> Result<Option<OpenaiConfig>, BiError> {
         self.get_config(OPENAI_CONFIG_KEY).await
@@ -310,6 +899,16 @@ impl Database {
         self.set_config(ANTHROPIC_CONFIG_KEY, &config).await
     }
 
+    // Get HTTP client config from db
+    pub async fn get_http_config(&self) -> Result<Option<HttpConfig>, BiError> {
+        self.get_config(HTTP_CONFIG_KEY).await
+    }
+
+    // Set HTTP client config in db
+    pub async fn set_http_config(&self, config: HttpConfig) -> Result<(), BiError> {
+        self.set_config(HTTP_CONFIG_KEY, &config).await
+    }
+
     // Get default AI provider
     pub async fn get_default_ai_provider(&self) -> Result<Option<AiProvider>, BiError> {
         self.get_config(DEFAULT_AI_PROVIDER_KEY).await
@@ -325,7 +924,9 @@ impl Database {
         &self,
         key: &str,
     ) -> Result<Option<T>, BiError> {
-        let row = query("SELECT value FROM settings WHERE key = ?")
+        let profile = self.settings_profile(key);
+        let row = query("SELECT value FROM settings WHERE profile = ? AND key = ?")
+            .bind(profile)
             .bind(key)
             .fetch_optional(&self.pool)
             .await
@@ -335,8 +936,24 @@ impl Database {
             let value: String = row
                 .try_get("value")
                 .map_err(|e| BiError::StringError(e.to_string()))?;
-            let config: T =
+            let mut value: Value =
                 serde_json::from_str(&value).map_err(|e| BiError::StringError(e.to_string()))?;
+            if let Value::Object(fields) = &mut value {
+                for field in Self::secret_fields(key) {
+                    if fields.contains_key(*field) {
+                        continue;
+                    }
+                    if let Some(secret) = self
+                        .secrets
+                        .get(&setting_secret_key(profile, key, field))
+                        .await?
+                    {
+                        fields.insert(field.to_string(), Value::String(secret));
+                    }
+                }
+            }
+            let config: T =
+                serde_json::from_value(value).map_err(|e| BiError::StringError(e.to_string()))?;
             Ok(Some(config))
         } else {
             Ok(None)
@@ -345,9 +962,22 @@ impl Database {
 
     // Helper function to set a configuration in the settings table
     async fn set_config<T: Serialize>(&self, key: &str, config: &T) -> Result<(), BiError> {
+        let profile = self.settings_profile(key);
+        let mut value =
+            serde_json::to_value(config).map_err(|e| BiError::StringError(e.to_string()))?;
+        if let Value::Object(fields) = &mut value {
+            for field in Self::secret_fields(key) {
+                if let Some(Value::String(secret)) = fields.remove(*field) {
+                    self.secrets
+                        .set(&setting_secret_key(profile, key, field), &secret)
+                        .await?;
+                }
+            }
+        }
         let value =
-            serde_json::to_string(config).map_err(|e| BiError::StringError(e.to_string()))?;
-        query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+            serde_json::to_string(&value).map_err(|e| BiError::StringError(e.to_string()))?;
+        query("INSERT OR REPLACE INTO settings (profile, key, value) VALUES (?, ?, ?)")
+            .bind(profile)
             .bind(key)
             .bind(value)
             .execute(&self.pool)
@@ -355,4 +985,22 @@ impl Database {
             .map_err(|e| BiError::StringError(e.to_string()))?;
         Ok(())
     }
+
+    // Profile a setting is stored under. Global settings use the empty profile name.
+    fn settings_profile(&self, key: &str) -> &str {
+        if GLOBAL_SETTINGS_KEYS.contains(&key) {
+            ""
+        } else {
+            &self.profile
+        }
+    }
+
+    // Secret fields of a settings entry
+    fn secret_fields(key: &str) -> &'static [&'static str] {
+        SECRET_SETTINGS_FIELDS
+            .iter()
+            .find(|(setting, _)| *setting == key)
+            .map(|(_, fields)| *fields)
+            .unwrap_or(&[])
+    }
 }
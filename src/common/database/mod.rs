@@ -1,4 +1,4 @@
 mod database;
 pub mod models;
 
-pub use database::Database;
+pub use database::{Database, DEFAULT_PROFILE, PROTECTED_TAG};
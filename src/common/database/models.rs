@@ -1,6 +1,7 @@
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use std::path::PathBuf;
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct Tenant {
@@ -41,6 +42,47 @@ pub struct OneloginConfig {
     pub client_secret: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EntraConfig {
+    pub tenant_id: String,
+    pub client_id: String,
+    pub client_secret: String,
+    #[serde(default = "EntraConfig::default_graph_base_url")]
+    pub graph_base_url: String,
+    #[serde(default = "EntraConfig::default_login_base_url")]
+    pub login_base_url: String,
+}
+
+impl EntraConfig {
+    pub fn default_graph_base_url() -> String {
+        "https://graph.microsoft.com/v1.0".to_string()
+    }
+
+    pub fn default_login_base_url() -> String {
+        "https://login.microsoftonline.com".to_string()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GoogleConfig {
+    pub service_account_key: String,
+    pub admin_email: String,
+    #[serde(default = "GoogleConfig::default_customer_id")]
+    pub customer_id: String,
+    #[serde(default = "GoogleConfig::default_api_base_url")]
+    pub api_base_url: String,
+}
+
+impl GoogleConfig {
+    pub fn default_customer_id() -> String {
+        "my_customer".to_string()
+    }
+
+    pub fn default_api_base_url() -> String {
+        "https://admin.googleapis.com".to_string()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct OpenaiConfig {
     pub api_key: String,
@@ -57,3 +99,92 @@ pub enum AiProvider {
     Openai,
     Anthropic,
 }
+
+/// Where client secrets and API keys are stored.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SecretsBackend {
+    /// The `secrets` table inside the local SQLite database, in plaintext.
+    /// Only kept by installs that stored secrets before the other backends.
+    #[default]
+    Database,
+    /// An AES-256-GCM encrypted file. The key is derived from the contents of
+    /// `key_file` when set, otherwise from a passphrase.
+    EncryptedFile {
+        path: PathBuf,
+        key_file: Option<PathBuf>,
+    },
+    /// The OS keyring (Secret Service, macOS Keychain or Windows Credential Manager).
+    Keyring,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct Profile {
+    pub name: String,
+    pub tenant_id: Option<String>,
+    pub realm_id: Option<String>,
+}
+
+/// A mutating API request recorded in the local audit journal.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    /// Unix timestamp of when the response was received
+    pub timestamp: i64,
+    pub os_user: String,
+    /// Command line of the invocation, with secret arguments redacted
+    pub command_line: String,
+    pub method: String,
+    pub url: String,
+    /// SHA-256 of the request body
+    pub body_hash: Option<String>,
+    /// Response status, or none if no response was received
+    pub status: Option<i64>,
+    /// ID of the created, changed or deleted resource
+    pub resource_id: Option<String>,
+    /// Resource body fetched just before a delete or patch
+    pub pre_image: Option<String>,
+    /// Request body, with secrets redacted
+    pub request_body: Option<String>,
+    /// Unix timestamp of when the action was undone
+    pub undone_at: Option<i64>,
+}
+
+/// An identity offboarded with `helper offboard`.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct Offboarding {
+    pub id: i64,
+    pub tenant_id: String,
+    pub realm_id: String,
+    pub identity_id: String,
+    /// Unix timestamp of the offboarding
+    pub offboarded_at: i64,
+    /// JSON record of every step taken
+    pub receipt: String,
+    /// Unix timestamp after which the identity is deleted by `helper purge-offboarded`
+    pub delete_after: Option<i64>,
+    /// Unix timestamp of when the identity was deleted
+    pub deleted_at: Option<i64>,
+    /// Unix timestamp of when the offboarding was reversed
+    pub reinstated_at: Option<i64>,
+}
+
+/// Settings shared by every HTTP client the CLI creates.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct HttpConfig {
+    /// Seconds to wait for a connection to be established
+    pub connect_timeout_secs: Option<u64>,
+    /// Seconds to wait for a whole request to complete
+    pub timeout_secs: Option<u64>,
+    /// Proxy used for all requests. Without it, HTTPS_PROXY, HTTP_PROXY and
+    /// NO_PROXY from the environment apply.
+    pub proxy: Option<String>,
+    /// Comma separated hosts that bypass `proxy`
+    pub no_proxy: Option<String>,
+    /// PEM bundle of root certificates trusted in addition to the built-in ones
+    pub ca_bundle: Option<PathBuf>,
+    /// PEM client certificate, optionally including its private key
+    pub client_cert: Option<PathBuf>,
+    /// PEM private key of the client certificate, if not part of `client_cert`
+    pub client_key: Option<PathBuf>,
+}
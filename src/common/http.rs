@@ -0,0 +1,78 @@
+use crate::common::database::models::HttpConfig;
+use crate::common::error::BiError;
+
+use reqwest::{Certificate, Client, Identity, NoProxy, Proxy};
+use std::sync::{Once, OnceLock};
+use std::time::Duration;
+
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_TIMEOUT_SECS: u64 = 600;
+
+// Config loaded from the settings table for this process
+static HTTP_CONFIG: OnceLock<HttpConfig> = OnceLock::new();
+
+// Set the config used by every client created in this process
+pub fn configure(config: HttpConfig) {
+    _ = HTTP_CONFIG.set(config);
+}
+
+// Whether the fallback to the default client was reported already
+static FALLBACK_WARNED: Once = Once::new();
+
+/// Create an HTTP client using the configured timeouts, proxy and certificates.
+/// Every outgoing request of the CLI should go through a client built here.
+/// When the configured settings can't be used, e.g. because a certificate file
+/// was removed, a warning is printed and the default settings are used instead,
+/// so that `bi config http` can still fix them.
+pub fn client() -> Client {
+    let config = HTTP_CONFIG.get().cloned().unwrap_or_default();
+    build_client(&config).unwrap_or_else(|err| {
+        FALLBACK_WARNED.call_once(|| {
+            eprintln!(
+                "Warning: ignoring the HTTP client settings ({}). Check `bi config http get`.",
+                err
+            );
+        });
+        build_client(&HttpConfig::default()).expect("Failed to build default HTTP client")
+    })
+}
+
+pub fn build_client(config: &HttpConfig) -> Result<Client, BiError> {
+    let mut builder = Client::builder()
+        .connect_timeout(Duration::from_secs(
+            config
+                .connect_timeout_secs
+                .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS),
+        ))
+        .timeout(Duration::from_secs(
+            config.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS),
+        ));
+
+    if let Some(proxy) = &config.proxy {
+        let no_proxy = config.no_proxy.as_deref().and_then(NoProxy::from_string);
+        builder = builder.proxy(Proxy::all(proxy)?.no_proxy(no_proxy));
+    }
+
+    if let Some(ca_bundle) = &config.ca_bundle {
+        let pem = read(ca_bundle)?;
+        for certificate in Certificate::from_pem_bundle(&pem)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    if let Some(client_cert) = &config.client_cert {
+        let mut pem = read(client_cert)?;
+        if let Some(client_key) = &config.client_key {
+            pem.push(b'\n');
+            pem.extend(read(client_key)?);
+        }
+        builder = builder.identity(Identity::from_pem(&pem)?);
+    }
+
+    Ok(builder.build()?)
+}
+
+fn read(path: &std::path::Path) -> Result<Vec<u8>, BiError> {
+    std::fs::read(path)
+        .map_err(|e| BiError::StringError(format!("Failed to read {}: {}", path.display(), e)))
+}
@@ -1,3 +1,6 @@
 pub mod command;
+pub mod csv;
 pub mod database;
 pub mod error;
+pub mod http;
+pub mod secrets;
@@ -0,0 +1,50 @@
+use super::SecretStore;
+
+use crate::common::error::BiError;
+
+use async_trait::async_trait;
+use sqlx::{query, query_as, sqlite::SqlitePool};
+
+/// Keeps secrets in the `secrets` table of the local SQLite database. This is
+/// the default backend and offers no protection beyond the file permissions
+/// of the database itself.
+pub struct DatabaseSecretStore {
+    pool: SqlitePool,
+}
+
+impl DatabaseSecretStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl SecretStore for DatabaseSecretStore {
+    async fn get(&self, key: &str) -> Result<Option<String>, BiError> {
+        let row = query_as::<_, (String,)>("SELECT value FROM secrets WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+        Ok(row.map(|(value,)| value))
+    }
+
+    async fn set(&self, key: &str, value: &str) -> Result<(), BiError> {
+        query("INSERT OR REPLACE INTO secrets (key, value) VALUES (?, ?)")
+            .bind(key)
+            .bind(value)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), BiError> {
+        query("DELETE FROM secrets WHERE key = ?")
+            .bind(key)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+use super::SecretStore;
+
+use crate::common::error::BiError;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// Environment variable consulted for the passphrase before prompting.
+pub const PASSPHRASE_ENV: &str = "BI_SECRETS_PASSPHRASE";
+
+const FORMAT_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+
+/// Keeps all secrets in a single AES-256-GCM encrypted JSON document. The
+/// encryption key is derived with Argon2id from either the contents of a key
+/// file or a passphrase. The file is decrypted once per process, on first use.
+pub struct EncryptedFileSecretStore {
+    path: PathBuf,
+    key_file: Option<PathBuf>,
+    state: Mutex<Option<Unlocked>>,
+}
+
+struct Unlocked {
+    salt: Vec<u8>,
+    cipher: Aes256Gcm,
+    secrets: BTreeMap<String, String>,
+}
+
+// On-disk layout of the encrypted file
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    version: u8,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl EncryptedFileSecretStore {
+    pub fn new(path: PathBuf, key_file: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            key_file,
+            state: Mutex::new(None),
+        }
+    }
+
+    // Decrypt the file on first access, or start an empty store if the file
+    // does not exist yet.
+    fn unlock(&self) -> Result<MutexGuard<'_, Option<Unlocked>>, BiError> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+        if state.is_some() {
+            return Ok(state);
+        }
+
+        let unlocked = if self.path.exists() {
+            let contents = std::fs::read_to_string(&self.path)
+                .map_err(|e| BiError::StringError(e.to_string()))?;
+            let envelope: Envelope = serde_json::from_str(&contents)?;
+            if envelope.version != FORMAT_VERSION {
+                return Err(BiError::StringError(format!(
+                    "Unsupported secrets file version {} in {}",
+                    envelope.version,
+                    self.path.display()
+                )));
+            }
+            let salt = decode(&envelope.salt)?;
+            let nonce = decode(&envelope.nonce)?;
+            let ciphertext = decode(&envelope.ciphertext)?;
+            let cipher = self.derive_cipher(&salt)?;
+            let plaintext = cipher
+                .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+                .map_err(|_| {
+                    BiError::StringError(format!(
+                        "Failed to decrypt {}. Check the passphrase or key file.",
+                        self.path.display()
+                    ))
+                })?;
+            Unlocked {
+                salt,
+                cipher,
+                secrets: serde_json::from_slice(&plaintext)?,
+            }
+        } else {
+            let mut salt = vec![0u8; SALT_LEN];
+            rand::thread_rng().fill_bytes(&mut salt);
+            let cipher = self.derive_cipher(&salt)?;
+            Unlocked {
+                salt,
+                cipher,
+                secrets: BTreeMap::new(),
+            }
+        };
+
+        *state = Some(unlocked);
+        Ok(state)
+    }
+
+    fn derive_cipher(&self, salt: &[u8]) -> Result<Aes256Gcm, BiError> {
+        let material = match &self.key_file {
+            Some(key_file) => {
+                std::fs::read(key_file).map_err(|e| BiError::StringError(e.to_string()))?
+            }
+            None => match std::env::var(PASSPHRASE_ENV) {
+                Ok(passphrase) => passphrase.into_bytes(),
+                Err(_) => {
+                    rpassword::prompt_password(format!("Passphrase for {}: ", self.path.display()))
+                        .map_err(|e| BiError::StringError(e.to_string()))?
+                        .into_bytes()
+                }
+            },
+        };
+        if material.is_empty() {
+            return Err(BiError::StringError(
+                "Secrets passphrase or key file must not be empty".to_string(),
+            ));
+        }
+
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(&material, salt, &mut key)
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+        Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+    }
+
+    // Encrypt the whole document with a fresh nonce and atomically replace the file.
+    fn persist(&self, unlocked: &Unlocked) -> Result<(), BiError> {
+        let plaintext = serde_json::to_vec(&unlocked.secrets)?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = unlocked
+            .cipher
+            .encrypt(&nonce, plaintext.as_ref())
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+        let envelope = Envelope {
+            version: FORMAT_VERSION,
+            salt: STANDARD.encode(&unlocked.salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        };
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| BiError::StringError(e.to_string()))?;
+        }
+        let tmp_path = self.path.with_extension("tmp");
+        write_private(&tmp_path, &serde_json::to_vec_pretty(&envelope)?)?;
+        std::fs::rename(&tmp_path, &self.path).map_err(|e| BiError::StringError(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl SecretStore for EncryptedFileSecretStore {
+    async fn get(&self, key: &str) -> Result<Option<String>, BiError> {
+        let state = self.unlock()?;
+        Ok(state.as_ref().and_then(|u| u.secrets.get(key).cloned()))
+    }
+
+    async fn set(&self, key: &str, value: &str) -> Result<(), BiError> {
+        let mut state = self.unlock()?;
+        if let Some(unlocked) = state.as_mut() {
+            unlocked.secrets.insert(key.to_string(), value.to_string());
+            self.persist(unlocked)?;
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), BiError> {
+        let mut state = self.unlock()?;
+        if let Some(unlocked) = state.as_mut() {
+            if unlocked.secrets.remove(key).is_some() {
+                self.persist(unlocked)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, BiError> {
+    STANDARD
+        .decode(value)
+        .map_err(|e| BiError::StringError(e.to_string()))
+}
+
+// Write a file that only the current user can read.
+fn write_private(path: &Path, contents: &[u8]) -> Result<(), BiError> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .map_err(|e| BiError::StringError(e.to_string()))?;
+    file.write_all(contents)
+        .map_err(|e| BiError::StringError(e.to_string()))
+}
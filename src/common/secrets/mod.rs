@@ -0,0 +1,88 @@
+mod database;
+mod encrypted_file;
+mod os_keyring;
+
+use crate::common::database::models::SecretsBackend;
+use crate::common::database::{Database, DEFAULT_PROFILE};
+use crate::common::error::BiError;
+
+use async_trait::async_trait;
+use sqlx::sqlite::SqlitePool;
+use std::sync::Arc;
+
+pub use database::DatabaseSecretStore;
+pub use encrypted_file::EncryptedFileSecretStore;
+pub use os_keyring::KeyringSecretStore;
+
+/// Storage for client secrets and API keys, addressed by a flat string key.
+#[async_trait]
+pub trait SecretStore: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<String>, BiError>;
+    async fn set(&self, key: &str, value: &str) -> Result<(), BiError>;
+    async fn delete(&self, key: &str) -> Result<(), BiError>;
+}
+
+/// Environment variable that picks the backend of a new data directory:
+/// `database`, `encrypted-file` or `keyring`.
+pub const BACKEND_ENV: &str = "BI_SECRETS_BACKEND";
+
+// Open the secret store for the given backend. The database backend shares
+// the connection pool of the settings database.
+pub fn open(backend: &SecretsBackend, pool: &SqlitePool) -> Result<Arc<dyn SecretStore>, BiError> {
+    Ok(match backend {
+        SecretsBackend::Database => Arc::new(DatabaseSecretStore::new(pool.clone())),
+        SecretsBackend::EncryptedFile { path, key_file } => Arc::new(
+            EncryptedFileSecretStore::new(path.clone(), key_file.clone()),
+        ),
+        SecretsBackend::Keyring => Arc::new(KeyringSecretStore::new(&keyring_service()?)),
+    })
+}
+
+// Keyring service of the current data directory, so that separate installs
+// never share or overwrite each other's entries.
+fn keyring_service() -> Result<String, BiError> {
+    Ok(format!(
+        "{} ({})",
+        env!("CARGO_PKG_NAME"),
+        Database::data_dir()?.display()
+    ))
+}
+
+// Backend for a new data directory: the one named by BI_SECRETS_BACKEND, else
+// the OS keyring when one is reachable, else the database.
+pub async fn default_backend() -> Result<SecretsBackend, BiError> {
+    match std::env::var(BACKEND_ENV).as_deref() {
+        Ok("database") => Ok(SecretsBackend::Database),
+        Ok("keyring") => Ok(SecretsBackend::Keyring),
+        Ok("encrypted-file") => Ok(SecretsBackend::EncryptedFile {
+            path: Database::data_dir()?.join("secrets.enc"),
+            key_file: None,
+        }),
+        Ok(other) => Err(BiError::StringError(format!(
+            "Unknown {} \"{}\", expected database, encrypted-file or keyring",
+            BACKEND_ENV, other
+        ))),
+        Err(_)
+            if KeyringSecretStore::new(&keyring_service()?)
+                .is_available()
+                .await =>
+        {
+            Ok(SecretsBackend::Keyring)
+        }
+        Err(_) => Ok(SecretsBackend::Database),
+    }
+}
+
+// Key under which the client secret of a realm is stored
+pub fn realm_secret_key(tenant_id: &str, realm_id: &str) -> String {
+    format!("realm/{}/{}/client_secret", tenant_id, realm_id)
+}
+
+// Key under which a secret field of a settings entry is stored. Global and
+// default profile settings keep the unscoped keys they had before profiles.
+pub fn setting_secret_key(profile: &str, setting: &str, field: &str) -> String {
+    match profile {
+        "" | DEFAULT_PROFILE => format!("settings/{}/{}", setting, field),
+        _ => format!("profiles/{}/settings/{}/{}", profile, setting, field),
+    }
+}
@@ -0,0 +1,65 @@
+use super::SecretStore;
+
+use crate::common::error::BiError;
+
+use async_trait::async_trait;
+
+/// Keeps secrets in the OS keyring: the Secret Service on Linux, the Keychain
+/// on macOS and the Credential Manager on Windows.
+pub struct KeyringSecretStore {
+    service: String,
+}
+
+impl KeyringSecretStore {
+    pub fn new(service: &str) -> Self {
+        Self {
+            service: service.to_string(),
+        }
+    }
+
+    // Whether a keyring can be reached, e.g. not on a headless machine without
+    // a Secret Service.
+    pub async fn is_available(&self) -> bool {
+        self.get("probe").await.is_ok()
+    }
+
+    // Keyring calls block on platform IPC, so run them off the async runtime.
+    async fn with_entry<T, F>(&self, key: &str, f: F) -> Result<T, BiError>
+    where
+        T: Send + 'static,
+        F: FnOnce(::keyring::Entry) -> Result<T, ::keyring::Error> + Send + 'static,
+    {
+        let service = self.service.clone();
+        let key = key.to_string();
+        tokio::task::spawn_blocking(move || f(::keyring::Entry::new(&service, &key)?))
+            .await
+            .map_err(|e| BiError::StringError(e.to_string()))?
+            .map_err(|e| BiError::StringError(format!("Keyring error: {}", e)))
+    }
+}
+
+#[async_trait]
+impl SecretStore for KeyringSecretStore {
+    async fn get(&self, key: &str) -> Result<Option<String>, BiError> {
+        self.with_entry(key, |entry| match entry.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(::keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e),
+        })
+        .await
+    }
+
+    async fn set(&self, key: &str, value: &str) -> Result<(), BiError> {
+        let value = value.to_string();
+        self.with_entry(key, move |entry| entry.set_password(&value))
+            .await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), BiError> {
+        self.with_entry(key, |entry| match entry.delete_credential() {
+            Ok(()) | Err(::keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e),
+        })
+        .await
+    }
+}
@@ -1,6 +1,11 @@
 use super::ai::command::Ai;
+use super::entra::command::EntraConfigCommands;
+use super::google::command::GoogleConfigCommands;
+use super::http::command::HttpConfigCommands;
 use super::okta::command::OktaConfigCommands;
 use super::onelogin::command::OneloginConfigCommands;
+use super::profiles::command::ProfilesConfigCommands;
+use super::secrets::command::SecretsConfigCommands;
 use super::tenants::command::Tenants;
 
 use crate::common::command::{ambassador_impl_Executable, Executable};
@@ -16,6 +21,14 @@ pub enum ConfigCommands {
     #[clap(subcommand)]
     Tenants(Tenants),
 
+    /// Manage named profiles bundling a tenant/realm with its migration source settings
+    #[clap(subcommand)]
+    Profiles(ProfilesConfigCommands),
+
+    /// Configure timeouts, proxy and certificates of the HTTP client
+    #[clap(subcommand)]
+    Http(HttpConfigCommands),
+
     /// Commands for configuring the AI helper tool
     #[clap(subcommand)]
     Ai(Ai),
@@ -27,4 +40,20 @@ pub enum ConfigCommands {
     /// Configure Onelogin settings to enable the CLI tool to interact with Onelogin APIs
     #[clap(subcommand)]
     Onelogin(OneloginConfigCommands),
+
+    /// Configure Microsoft Entra ID settings to enable the CLI tool to interact with Microsoft Graph APIs
+    #[clap(subcommand)]
+    Entra(EntraConfigCommands),
+
+    /// Configure Google Workspace settings to enable the CLI tool to interact with the Admin SDK Directory API
+    #[clap(subcommand)]
+    Google(GoogleConfigCommands),
+
+    /// Configure where client secrets and API keys are stored
+    ///
+    /// A new data directory uses the OS keyring when one is available and the
+    /// local database otherwise. Set BI_SECRETS_BACKEND to `database`,
+    /// `encrypted-file` or `keyring` to choose it instead.
+    #[clap(subcommand)]
+    Secrets(SecretsConfigCommands),
 }
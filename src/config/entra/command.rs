@@ -0,0 +1,97 @@
+use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::common::command::ambassador_impl_Executable;
+use crate::common::database::models::EntraConfig;
+use crate::common::{command::Executable, error::BiError};
+
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+
+// ====================================
+// Entra Commands
+// ====================================
+
+/// Actions for configuring and managing Microsoft Entra ID integration settings.
+#[derive(Subcommand, ambassador::Delegate)]
+#[delegate(Executable)]
+pub enum EntraConfigCommands {
+    /// Configure Microsoft Entra ID integration settings.
+    Set(Set),
+
+    /// Display current Microsoft Entra ID integration settings.
+    Get(Get),
+}
+
+// ====================================
+// Entra Set
+// ====================================
+
+#[derive(Args)]
+pub struct Set {
+    /// Entra directory (tenant) ID
+    #[clap(long)]
+    tenant_id: String,
+
+    /// Client ID of the app registration used to read from Microsoft Graph
+    #[clap(long)]
+    client_id: String,
+
+    /// Client secret of the app registration used to read from Microsoft Graph
+    #[clap(long)]
+    client_secret: String,
+
+    /// Microsoft Graph base URL. Override to point at a local mock Graph server.
+    #[clap(long, default_value_t = EntraConfig::default_graph_base_url())]
+    graph_base_url: String,
+
+    /// Microsoft identity platform base URL used to fetch access tokens.
+    #[clap(long, default_value_t = EntraConfig::default_login_base_url())]
+    login_base_url: String,
+
+    /// Flag to allow force reconfiguration
+    #[arg(long)]
+    force: bool,
+}
+
+#[async_trait]
+impl Executable for Set {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        if let Ok(Some(c)) = api_client.db.get_entra_config().await {
+            if !self.force {
+                println!("Already configured: {:?}", c);
+                return Ok(());
+            } else {
+                println!("Forcing reconfiguration...");
+            }
+        }
+        let entra_config = EntraConfig {
+            tenant_id: self.tenant_id.to_string(),
+            client_id: self.client_id.to_string(),
+            client_secret: self.client_secret.to_string(),
+            graph_base_url: self.graph_base_url.trim_end_matches('/').to_string(),
+            login_base_url: self.login_base_url.trim_end_matches('/').to_string(),
+        };
+        Ok(api_client.db.set_entra_config(entra_config).await?)
+    }
+}
+
+// ====================================
+// Entra Get
+// ====================================
+
+#[derive(Args)]
+pub struct Get;
+
+#[async_trait]
+impl Executable for Get {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        if let Ok(Some(c)) = api_client.db.get_entra_config().await {
+            println!("{:?}", c);
+            return Ok(());
+        }
+        Err(BiError::StringError(
+            "Microsoft Entra ID not yet configured".to_string(),
+        ))
+    }
+}
@@ -0,0 +1,98 @@
+use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::common::command::ambassador_impl_Executable;
+use crate::common::database::models::GoogleConfig;
+use crate::common::{command::Executable, error::BiError};
+
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+// ====================================
+// Google Commands
+// ====================================
+
+/// Actions for configuring and managing Google Workspace integration settings.
+#[derive(Subcommand, ambassador::Delegate)]
+#[delegate(Executable)]
+pub enum GoogleConfigCommands {
+    /// Configure Google Workspace integration settings.
+    Set(Set),
+
+    /// Display current Google Workspace integration settings.
+    Get(Get),
+}
+
+// ====================================
+// Google Set
+// ====================================
+
+#[derive(Args)]
+pub struct Set {
+    /// Path to the service account JSON key. The service account must have domain-wide
+    /// delegation for the Admin SDK Directory API read-only user and group scopes.
+    #[clap(long)]
+    service_account_key_file: PathBuf,
+
+    /// Email of a Google Workspace administrator the service account impersonates
+    #[clap(long)]
+    admin_email: String,
+
+    /// Google Workspace customer ID
+    #[clap(long, default_value_t = GoogleConfig::default_customer_id())]
+    customer_id: String,
+
+    /// Admin SDK base URL. Override to point at a local stub server.
+    #[clap(long, default_value_t = GoogleConfig::default_api_base_url())]
+    api_base_url: String,
+
+    /// Flag to allow force reconfiguration
+    #[arg(long)]
+    force: bool,
+}
+
+#[async_trait]
+impl Executable for Set {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        if let Ok(Some(c)) = api_client.db.get_google_config().await {
+            if !self.force {
+                println!("Already configured: {:?}", c);
+                return Ok(());
+            } else {
+                println!("Forcing reconfiguration...");
+            }
+        }
+        let service_account_key = std::fs::read_to_string(&self.service_account_key_file)
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+        // Fail early on a malformed key rather than on the first migration.
+        serde_json::from_str::<serde_json::Value>(&service_account_key)?;
+        let google_config = GoogleConfig {
+            service_account_key,
+            admin_email: self.admin_email.to_string(),
+            customer_id: self.customer_id.to_string(),
+            api_base_url: self.api_base_url.trim_end_matches('/').to_string(),
+        };
+        Ok(api_client.db.set_google_config(google_config).await?)
+    }
+}
+
+// ====================================
+// Google Get
+// ====================================
+
+#[derive(Args)]
+pub struct Get;
+
+#[async_trait]
+impl Executable for Get {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        if let Ok(Some(c)) = api_client.db.get_google_config().await {
+            println!("{:?}", c);
+            return Ok(());
+        }
+        Err(BiError::StringError(
+            "Google Workspace not yet configured".to_string(),
+        ))
+    }
+}
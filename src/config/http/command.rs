@@ -0,0 +1,137 @@
+use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::common::command::ambassador_impl_Executable;
+use crate::common::database::models::HttpConfig;
+use crate::common::http::build_client;
+use crate::common::{command::Executable, error::BiError};
+
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+// ====================================
+// HTTP Commands
+// ====================================
+
+/// Actions for configuring the HTTP client used for every outgoing request.
+#[derive(Subcommand, ambassador::Delegate)]
+#[delegate(Executable)]
+pub enum HttpConfigCommands {
+    /// Update HTTP client settings. Settings that are not passed keep their current value.
+    Set(Set),
+
+    /// Display current HTTP client settings.
+    Get(Get),
+
+    /// Restore the default HTTP client settings.
+    Reset(Reset),
+}
+
+// ====================================
+// HTTP Set
+// ====================================
+
+#[derive(Args)]
+pub struct Set {
+    /// Seconds to wait for a connection to be established (default 30)
+    #[clap(long)]
+    connect_timeout: Option<u64>,
+
+    /// Seconds to wait for a whole request to complete (default 600)
+    #[clap(long)]
+    timeout: Option<u64>,
+
+    /// Proxy URL used for all requests, e.g. http://proxy.corp:3128. When unset,
+    /// HTTPS_PROXY, HTTP_PROXY and NO_PROXY are read from the environment.
+    #[clap(long)]
+    proxy: Option<String>,
+
+    /// Comma separated hosts that bypass the proxy, e.g. localhost,.corp
+    #[clap(long)]
+    no_proxy: Option<String>,
+
+    /// PEM bundle of extra root certificates to trust, e.g. a corporate root CA
+    #[clap(long)]
+    ca_bundle: Option<PathBuf>,
+
+    /// PEM client certificate presented to servers requesting mutual TLS
+    #[clap(long)]
+    client_cert: Option<PathBuf>,
+
+    /// PEM private key of the client certificate, if not included in --client-cert
+    #[clap(long, requires = "client_cert")]
+    client_key: Option<PathBuf>,
+}
+
+fn absolute(path: &Option<PathBuf>) -> Result<Option<PathBuf>, BiError> {
+    path.as_ref()
+        .map(|p| std::path::absolute(p).map_err(|e| BiError::StringError(e.to_string())))
+        .transpose()
+}
+
+#[async_trait]
+impl Executable for Set {
+    async fn execute(&self) -> Result<(), BiError> {
+        let db = ApiClient::new(None, None).await.db;
+        let mut config = db.get_http_config().await?.unwrap_or_default();
+
+        if self.connect_timeout.is_some() {
+            config.connect_timeout_secs = self.connect_timeout;
+        }
+        if self.timeout.is_some() {
+            config.timeout_secs = self.timeout;
+        }
+        if self.proxy.is_some() {
+            config.proxy = self.proxy.clone();
+        }
+        if self.no_proxy.is_some() {
+            config.no_proxy = self.no_proxy.clone();
+        }
+        if self.ca_bundle.is_some() {
+            config.ca_bundle = absolute(&self.ca_bundle)?;
+        }
+        if self.client_cert.is_some() {
+            config.client_cert = absolute(&self.client_cert)?;
+            config.client_key = absolute(&self.client_key)?;
+        }
+
+        // Fail early on an unreadable certificate or malformed proxy URL
+        build_client(&config)?;
+
+        db.set_http_config(config.clone()).await?;
+        println!("{:?}", config);
+        Ok(())
+    }
+}
+
+// ====================================
+// HTTP Get
+// ====================================
+
+#[derive(Args)]
+pub struct Get;
+
+#[async_trait]
+impl Executable for Get {
+    async fn execute(&self) -> Result<(), BiError> {
+        let db = ApiClient::new(None, None).await.db;
+        println!("{:?}", db.get_http_config().await?.unwrap_or_default());
+        Ok(())
+    }
+}
+
+// ====================================
+// HTTP Reset
+// ====================================
+
+#[derive(Args)]
+pub struct Reset;
+
+#[async_trait]
+impl Executable for Reset {
+    async fn execute(&self) -> Result<(), BiError> {
+        let db = ApiClient::new(None, None).await.db;
+        db.set_http_config(HttpConfig::default()).await?;
+        println!("{:?}", HttpConfig::default());
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
 pub mod ai;
 pub mod command;
+pub mod entra;
+pub mod google;
+pub mod http;
 pub mod tenants;
 pub mod okta;
 pub mod onelogin;
+pub mod profiles;
+pub mod secrets;
@@ -0,0 +1,220 @@
+use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::common::command::ambassador_impl_Executable;
+use crate::common::database::DEFAULT_PROFILE;
+use crate::common::{command::Executable, error::BiError};
+
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+use tabled::settings::style::Style;
+use tabled::{Table, Tabled};
+
+// ====================================
+// Profiles Commands
+// ====================================
+
+/// Actions for managing named profiles. A profile bundles a default tenant/realm
+/// with its own Okta, OneLogin, Entra and Google settings. Select a profile for a
+/// single command with `--profile` or the BI_PROFILE environment variable.
+#[derive(Subcommand, ambassador::Delegate)]
+#[delegate(Executable)]
+pub enum ProfilesConfigCommands {
+    /// Create a new profile.
+    Create(Create),
+
+    /// Make a profile the active one for all subsequent commands.
+    Use(Use),
+
+    /// Display all profiles.
+    List(List),
+
+    /// Delete a profile and its settings.
+    Delete(Delete),
+}
+
+fn validate_profile_name(name: &str) -> Result<String, String> {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(name.to_string())
+    } else {
+        Err("Profile names may only contain letters, digits, '-' and '_'".to_string())
+    }
+}
+
+// ====================================
+// Profiles Create
+// ====================================
+
+#[derive(Args)]
+pub struct Create {
+    /// Name of the profile
+    #[clap(value_parser = validate_profile_name)]
+    name: String,
+
+    /// Default tenant of the profile. The tenant/realm must already be configured.
+    #[clap(long, requires = "realm_id")]
+    tenant_id: Option<String>,
+
+    /// Default realm of the profile
+    #[clap(long, requires = "tenant_id")]
+    realm_id: Option<String>,
+}
+
+#[async_trait]
+impl Executable for Create {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        let db = &api_client.db;
+
+        if db.get_profiles().await?.iter().any(|p| p.name == self.name) {
+            return Err(BiError::StringError(format!(
+                "Profile '{}' already exists",
+                self.name
+            )));
+        }
+
+        if let (Some(tenant_id), Some(realm_id)) = (&self.tenant_id, &self.realm_id) {
+            let configured = db
+                .get_all_tenants_with_realms()
+                .await?
+                .iter()
+                .any(|(t, realms)| &t.id == tenant_id && realms.iter().any(|r| &r.id == realm_id));
+            if !configured {
+                return Err(BiError::StringError(format!(
+                    "Tenant {} realm {} is not configured. Add it with `bi config tenants add` first.",
+                    tenant_id, realm_id
+                )));
+            }
+        }
+
+        db.create_profile(
+            &self.name,
+            self.tenant_id.as_deref(),
+            self.realm_id.as_deref(),
+        )
+        .await?;
+        println!("Created profile '{}'", self.name);
+        Ok(())
+    }
+}
+
+// ====================================
+// Profiles Use
+// ====================================
+
+#[derive(Args)]
+pub struct Use {
+    /// Name of the profile
+    name: String,
+}
+
+#[async_trait]
+impl Executable for Use {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        let db = &api_client.db;
+
+        if !db.get_profiles().await?.iter().any(|p| p.name == self.name) {
+            return Err(BiError::StringError(format!(
+                "Profile '{}' does not exist",
+                self.name
+            )));
+        }
+
+        db.set_active_profile(&self.name).await?;
+        println!("Using profile '{}'", self.name);
+        Ok(())
+    }
+}
+
+// ====================================
+// Profiles List
+// ====================================
+
+#[derive(Tabled)]
+struct ProfileDisplay {
+    #[tabled(rename = "Active")]
+    active: String,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Tenant ID")]
+    tenant_id: String,
+    #[tabled(rename = "Realm ID")]
+    realm_id: String,
+    #[tabled(rename = "Settings")]
+    settings: String,
+}
+
+#[derive(Args)]
+pub struct List;
+
+#[async_trait]
+impl Executable for List {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        let db = &api_client.db;
+
+        let mut display = vec![];
+        for profile in db.get_profiles().await? {
+            let settings = db.get_profile_settings_keys(&profile.name).await?;
+            display.push(ProfileDisplay {
+                active: if profile.name == db.profile() {
+                    "*".to_string()
+                } else {
+                    "".to_string()
+                },
+                name: profile.name,
+                tenant_id: profile.tenant_id.unwrap_or_default(),
+                realm_id: profile.realm_id.unwrap_or_default(),
+                settings: settings.join(", "),
+            });
+        }
+
+        let mut table = Table::new(display);
+        table.with(Style::extended());
+        println!("{}", table);
+        Ok(())
+    }
+}
+
+// ====================================
+// Profiles Delete
+// ====================================
+
+#[derive(Args)]
+pub struct Delete {
+    /// Name of the profile
+    name: String,
+}
+
+#[async_trait]
+impl Executable for Delete {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        let db = &api_client.db;
+
+        if self.name == DEFAULT_PROFILE {
+            return Err(BiError::StringError(
+                "The default profile cannot be deleted".to_string(),
+            ));
+        }
+        if self.name == db.get_active_profile().await? {
+            return Err(BiError::StringError(format!(
+                "Profile '{}' is active. Switch to another profile with `bi config profiles use` first.",
+                self.name
+            )));
+        }
+        if !db.get_profiles().await?.iter().any(|p| p.name == self.name) {
+            return Err(BiError::StringError(format!(
+                "Profile '{}' does not exist",
+                self.name
+            )));
+        }
+
+        db.delete_profile(&self.name).await?;
+        println!("Deleted profile '{}'", self.name);
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::common::command::ambassador_impl_Executable;
+use crate::common::database::models::SecretsBackend;
+use crate::common::database::Database;
+use crate::common::{command::Executable, error::BiError};
+
+use async_trait::async_trait;
+use clap::{Args, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+// ====================================
+// Secrets Commands
+// ====================================
+
+/// Actions for managing where client secrets and API keys are stored.
+#[derive(Subcommand, ambassador::Delegate)]
+#[delegate(Executable)]
+pub enum SecretsConfigCommands {
+    /// Move all stored secrets into another backend and make it the active one.
+    Migrate(Migrate),
+
+    /// Display the active secrets backend.
+    Get(Get),
+}
+
+#[derive(Clone, ValueEnum)]
+pub enum BackendKind {
+    /// Plaintext `secrets` table in the local database
+    Database,
+    /// AES-256-GCM encrypted file protected by a passphrase or key file
+    EncryptedFile,
+    /// OS keyring (Secret Service, macOS Keychain, Windows Credential Manager)
+    Keyring,
+}
+
+// ====================================
+// Secrets Migrate
+// ====================================
+
+/// The passphrase for the encrypted file backend is read from the
+/// BI_SECRETS_PASSPHRASE environment variable, or prompted for when unset.
+#[derive(Args)]
+pub struct Migrate {
+    /// Backend to move secrets into
+    #[clap(long, value_enum)]
+    backend: BackendKind,
+
+    /// Location of the encrypted file. Defaults to `secrets.enc` in the data directory.
+    #[clap(long)]
+    path: Option<PathBuf>,
+
+    /// Derive the encryption key from this file instead of a passphrase
+    #[clap(long)]
+    key_file: Option<PathBuf>,
+}
+
+#[async_trait]
+impl Executable for Migrate {
+    async fn execute(&self) -> Result<(), BiError> {
+        let backend = match self.backend {
+            BackendKind::Database => SecretsBackend::Database,
+            BackendKind::Keyring => SecretsBackend::Keyring,
+            BackendKind::EncryptedFile => {
+                let path = match &self.path {
+                    Some(path) => path.clone(),
+                    None => Database::data_dir()?.join("secrets.enc"),
+                };
+                let path =
+                    std::path::absolute(&path).map_err(|e| BiError::StringError(e.to_string()))?;
+                let key_file = match &self.key_file {
+                    Some(key_file) => Some(
+                        std::path::absolute(key_file)
+                            .map_err(|e| BiError::StringError(e.to_string()))?,
+                    ),
+                    None => None,
+                };
+                SecretsBackend::EncryptedFile { path, key_file }
+            }
+        };
+        if !matches!(self.backend, BackendKind::EncryptedFile)
+            && (self.path.is_some() || self.key_file.is_some())
+        {
+            return Err(BiError::StringError(
+                "--path and --key-file only apply to the encrypted-file backend".to_string(),
+            ));
+        }
+
+        let api_client = ApiClient::new(None, None).await;
+        let moved = api_client.db.migrate_secrets(backend.clone()).await?;
+        println!("Moved {} secret(s) to {:?}", moved, backend);
+        Ok(())
+    }
+}
+
+// ====================================
+// Secrets Get
+// ====================================
+
+#[derive(Args)]
+pub struct Get;
+
+#[async_trait]
+impl Executable for Get {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        println!("{:?}", api_client.db.get_secrets_backend().await?);
+        Ok(())
+    }
+}
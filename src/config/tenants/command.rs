@@ -1,4 +1,7 @@
-use super::tenant::{delete_tenant_ui, list_tenants_ui, provision_tenant, set_default_tenant_ui};
+use super::tenant::{
+    delete_tenant_ui, list_tenants_ui, provision_tenant, provision_tenant_from_credentials,
+    set_default_tenant_ui, ClientCredentials,
+};
 
 use crate::{
     beyond_identity::api::common::{
@@ -6,18 +9,20 @@ use crate::{
     },
     common::{
         command::{ambassador_impl_Executable, Executable},
+        database::Database,
         error::BiError,
     },
 };
 
 use async_trait::async_trait;
-use clap::{Args, Subcommand};
+use clap::{ArgGroup, Args, Subcommand, ValueEnum};
 
 /// Actions for managing tenant configurations.
 #[derive(Subcommand, ambassador::Delegate)]
 #[delegate(Executable)]
 pub enum Tenants {
-    /// Provision an existing tenant using the provided API token.
+    /// Provision an existing tenant using the provided API token, or non-interactively
+    /// from the client credentials of the realm's management API application.
     Add(Add),
 
     /// Display a list of all configured tenants.
@@ -29,6 +34,13 @@ pub enum Tenants {
 
     /// Remove a tenant from the configured list.
     Remove(Remove),
+
+    /// Tag a configured realm. Realms tagged "protected" refuse bulk deletes
+    /// such as `helper delete-all-identities` unless explicitly overridden.
+    Tag(Tag),
+
+    /// Remove a tag from a configured realm.
+    Untag(Untag),
 }
 
 /// Actions for managing the default tenant/realm.
@@ -43,23 +55,112 @@ pub enum DefaultCommands {
 }
 
 #[derive(Args)]
+#[clap(group(ArgGroup::new("base_url").args(["region", "api_base_url"])))]
 pub struct Add {
     /// The API token associated with the tenant/realm you would like to add.
-    #[clap(long)]
-    token: String,
+    /// Takes precedence over client credentials set in the environment.
+    #[clap(long, required_unless_present = "client_id")]
+    token: Option<String>,
+
+    /// Skip verifying the token signature and issuer against the issuer's JWKS.
+    /// Only intended for local development.
+    #[clap(long, requires = "token")]
+    insecure_skip_verify: bool,
+
+    /// Tenant ID, when provisioning from client credentials
+    #[clap(long, env = "BI_TENANT_ID")]
+    tenant_id: Option<String>,
+
+    /// Realm ID, when provisioning from client credentials
+    #[clap(long, env = "BI_REALM_ID")]
+    realm_id: Option<String>,
+
+    /// ID of the management API application, when provisioning from client credentials
+    #[clap(long, env = "BI_APPLICATION_ID")]
+    application_id: Option<String>,
+
+    /// Client ID of the management API application. Not read from the
+    /// environment, so exported credentials never get in the way of `--token`.
+    #[clap(
+        long,
+        requires_all = ["tenant_id", "realm_id", "application_id", "client_secret", "base_url"]
+    )]
+    client_id: Option<String>,
+
+    /// Client secret of the management API application
+    #[clap(long, env = "BI_CLIENT_SECRET", hide_env_values = true)]
+    client_secret: Option<String>,
+
+    /// Region the tenant is hosted in
+    #[clap(long, env = "BI_REGION", value_enum)]
+    region: Option<Region>,
+
+    /// API base URL, for tenants outside the public regions
+    #[clap(long, env = "BI_API_BASE_URL")]
+    api_base_url: Option<String>,
+
+    /// Auth base URL, required with --api-base-url
+    #[clap(long, env = "BI_AUTH_BASE_URL")]
+    auth_base_url: Option<String>,
+}
+
+#[derive(Clone, ValueEnum)]
+pub enum Region {
+    Us,
+    Eu,
+}
+
+impl Region {
+    fn api_base_url(&self) -> String {
+        match self {
+            Region::Us => "https://api-us.beyondidentity.com".to_string(),
+            Region::Eu => "https://api-eu.beyondidentity.com".to_string(),
+        }
+    }
+
+    fn auth_base_url(&self) -> String {
+        match self {
+            Region::Us => "https://auth-us.beyondidentity.com".to_string(),
+            Region::Eu => "https://auth-eu.beyondidentity.com".to_string(),
+        }
+    }
 }
 
 #[async_trait]
 impl Executable for Add {
     async fn execute(&self) -> Result<(), BiError> {
         let api_client = ApiClient::new(None, None).await;
-        _ = provision_tenant(
-            &RespectRateLimitMiddleware::new_client(),
-            &api_client.db,
-            &self.token,
-        )
-        .await
-        .expect("Failed to provision tenant/realm");
+        let client = RespectRateLimitMiddleware::new_client();
+
+        if let Some(token) = &self.token {
+            provision_tenant(&client, &api_client.db, token, self.insecure_skip_verify).await?;
+            return Ok(());
+        }
+
+        // clap guarantees every credential is present once the token is absent
+        let (api_base_url, auth_base_url) =
+            match (&self.region, &self.api_base_url, &self.auth_base_url) {
+                (_, Some(api_base_url), Some(auth_base_url)) => {
+                    (api_base_url.clone(), auth_base_url.clone())
+                }
+                (_, Some(_), None) => {
+                    return Err(BiError::StringError(
+                        "--auth-base-url is required with --api-base-url".to_string(),
+                    ))
+                }
+                (Some(region), None, _) => (region.api_base_url(), region.auth_base_url()),
+                (None, None, _) => unreachable!(),
+            };
+        let credentials = ClientCredentials {
+            tenant_id: self.tenant_id.clone().unwrap_or_default(),
+            realm_id: self.realm_id.clone().unwrap_or_default(),
+            application_id: self.application_id.clone().unwrap_or_default(),
+            client_id: self.client_id.clone().unwrap_or_default(),
+            client_secret: self.client_secret.clone().unwrap_or_default(),
+            api_base_url,
+            auth_base_url,
+        };
+        provision_tenant_from_credentials(&client, &api_client.db, credentials).await?;
         Ok(())
     }
 }
@@ -107,3 +208,89 @@ impl Executable for Remove {
         Ok(delete_tenant_ui(&api_client.db).await?)
     }
 }
+
+/// The realm to tag, defaulting to the default tenant/realm.
+#[derive(Args)]
+pub struct TagTarget {
+    /// Tenant ID of the realm. Defaults to the default tenant.
+    #[clap(long, requires = "realm_id")]
+    tenant_id: Option<String>,
+
+    /// Realm ID. Defaults to the default realm.
+    #[clap(long, requires = "tenant_id")]
+    realm_id: Option<String>,
+}
+
+impl TagTarget {
+    async fn resolve(&self, db: &Database) -> Result<(String, String), BiError> {
+        if let (Some(tenant_id), Some(realm_id)) = (&self.tenant_id, &self.realm_id) {
+            let configured = db
+                .get_all_tenants_with_realms()
+                .await?
+                .iter()
+                .any(|(t, realms)| &t.id == tenant_id && realms.iter().any(|r| &r.id == realm_id));
+            if !configured {
+                return Err(BiError::StringError(format!(
+                    "Tenant {} realm {} is not configured",
+                    tenant_id, realm_id
+                )));
+            }
+            return Ok((tenant_id.clone(), realm_id.clone()));
+        }
+        match db.get_default_tenant_and_realm().await? {
+            Some((tenant, realm)) => Ok((tenant.id, realm.id)),
+            None => Err(BiError::StringError(
+                "No default tenant/realm set".to_string(),
+            )),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct Tag {
+    /// Tag to add, e.g. "protected"
+    tag: String,
+
+    #[clap(flatten)]
+    target: TagTarget,
+}
+
+#[async_trait]
+impl Executable for Tag {
+    async fn execute(&self) -> Result<(), BiError> {
+        let db = ApiClient::new(None, None).await.db;
+        let (tenant_id, realm_id) = self.target.resolve(&db).await?;
+        db.add_realm_tag(&tenant_id, &realm_id, &self.tag).await?;
+        println!(
+            "Tenant {} realm {} is tagged: {}",
+            tenant_id,
+            realm_id,
+            db.get_realm_tags(&tenant_id, &realm_id).await?.join(", ")
+        );
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct Untag {
+    /// Tag to remove
+    tag: String,
+
+    #[clap(flatten)]
+    target: TagTarget,
+}
+
+#[async_trait]
+impl Executable for Untag {
+    async fn execute(&self) -> Result<(), BiError> {
+        let db = ApiClient::new(None, None).await.db;
+        let (tenant_id, realm_id) = self.target.resolve(&db).await?;
+        db.remove_realm_tag(&tenant_id, &realm_id, &self.tag)
+            .await?;
+        println!(
+            "Removed tag {} from tenant {} realm {}",
+            self.tag, tenant_id, realm_id
+        );
+        Ok(())
+    }
+}
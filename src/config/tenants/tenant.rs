@@ -1,4 +1,5 @@
 use crate::beyond_identity::api;
+use crate::beyond_identity::api::common::middleware::authorization::token;
 use crate::beyond_identity::api::common::service::{RealmsService, TenantsService};
 use crate::beyond_identity::api::realms::api::RealmsApi;
 use crate::beyond_identity::api::realms::types::Classification;
@@ -9,7 +10,8 @@ use crate::common::error::BiError;
 use crate::config::tenants::application::get_management_api_application;
 
 use futures::future::join_all;
-use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use jsonwebtoken::jwk::{AlgorithmParameters, JwkSet, KeyAlgorithm};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
 use reqwest_middleware::ClientWithMiddleware as Client;
 use serde::{Deserialize, Serialize};
 use std::io::{self, Write};
@@ -27,34 +29,31 @@ struct Claims {
     iss: String, // The issuer field in the JWT
 }
 
+#[derive(Debug, Deserialize)]
+struct OpenIdConfiguration {
+    issuer: String,
+    jwks_uri: String,
+}
+
+// Registrable domains that Beyond Identity issuers are served from
+const TRUSTED_ISSUER_DOMAINS: &[&str] = &["beyondidentity.com"];
+
+// Algorithm Beyond Identity signs API tokens with
+const TOKEN_ALGORITHM: Algorithm = Algorithm::RS256;
+
 pub async fn provision_tenant(
     client: &Client,
     db: &Database,
     token: &str,
+    insecure_skip_verify: bool,
 ) -> Result<(database::models::Tenant, database::models::Realm), BiError> {
-    let mut validation = Validation::new(Algorithm::RS256);
-    validation.insecure_disable_signature_validation();
-    validation.validate_aud = false;
-
-    // Decode the JWT (without signature verification)
-    let decoded_token = match decode::<Claims>(
-        &token,
-        &DecodingKey::from_secret("".as_ref()), // A dummy secret since we are disabling signature verification
-        &validation,
-    ) {
-        Ok(token) => token,
-        Err(err) => {
-            return Err(BiError::StringError(format!(
-                "Failed to decode JWT: {:?}",
-                err
-            )));
-        }
-    };
-
-    let claims = decoded_token.claims;
-
     // Extract the issuer (iss) from the claims
-    let issuer_url = claims.iss;
+    let issuer_url = if insecure_skip_verify {
+        println!("Skipping verification of the API token signature and issuer.");
+        decode_issuer_unverified(token)?
+    } else {
+        verify_token(client, token).await?
+    };
 
     // Parse the URL and extract tenant_id, realm_id, application_id
     let parsed_url = Url::parse(&issuer_url).map_err(BiError::InvalidUrl)?;
@@ -85,14 +84,19 @@ pub async fn provision_tenant(
     let management_api_application =
         get_management_api_application(client, &api_base_url, &tenant_id, &realm_id, token).await?;
 
-    let client_id = management_api_application
-        .protocol_config
-        .client_id
-        .expect("Failed to get client id of management API application");
+    let client_id =
+        management_api_application
+            .protocol_config
+            .client_id
+            .ok_or(BiError::StringError(
+                "The management API application has no client ID".to_string(),
+            ))?;
     let client_secret = management_api_application
         .protocol_config
         .client_secret
-        .expect("Failed to get client secret of management API application");
+        .ok_or(BiError::StringError(
+            "The management API application has no client secret".to_string(),
+        ))?;
 
     let tenant = database::models::Tenant {
         id: tenant_id.clone(),
@@ -109,9 +113,94 @@ pub async fn provision_tenant(
         auth_base_url,
     };
 
+    ensure_not_provisioned(db, &tenant_id, &realm_id).await?;
+    save_tenant_and_realm(db, &tenant, &realm).await?;
+
+    _ = display(db).await?;
+
+    Ok((tenant.clone(), realm.clone()))
+}
+
+/// Client credentials of the management API application of a realm.
+pub struct ClientCredentials {
+    pub tenant_id: String,
+    pub realm_id: String,
+    pub application_id: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub api_base_url: String,
+    pub auth_base_url: String,
+}
+
+// Provision a tenant/realm from the client credentials of its management API
+// application, without an admin token or any prompt. The credentials are
+// validated by fetching an access token before anything is stored.
+pub async fn provision_tenant_from_credentials(
+    client: &Client,
+    db: &Database,
+    credentials: ClientCredentials,
+) -> Result<(database::models::Tenant, database::models::Realm), BiError> {
+    let ClientCredentials {
+        tenant_id,
+        realm_id,
+        application_id,
+        client_id,
+        client_secret,
+        api_base_url,
+        auth_base_url,
+    } = credentials;
+    let origin = |url: &str| -> Result<String, BiError> {
+        Ok(Url::parse(url)
+            .map_err(BiError::InvalidUrl)?
+            .origin()
+            .ascii_serialization())
+    };
+    let api_base_url = origin(&api_base_url)?;
+    let auth_base_url = origin(&auth_base_url)?;
+
+    let tenant = database::models::Tenant {
+        id: tenant_id.clone(),
+    };
+
+    let realm = database::models::Realm {
+        id: realm_id.clone(),
+        tenant_id: tenant_id.clone(),
+        application_id: application_id.clone(),
+        client_id,
+        client_secret,
+        open_id_configuration_url: format!(
+            "{}/v1/tenants/{}/realms/{}/applications/{}/.well-known/openid-configuration",
+            auth_base_url, tenant_id, realm_id, application_id
+        ),
+        api_base_url,
+        auth_base_url,
+    };
+
+    ensure_not_provisioned(db, &tenant_id, &realm_id).await?;
+
+    // Never validate against a token left behind by an earlier provisioning
+    db.delete_token(&tenant_id, &realm_id).await?;
+    token(db, client, &Some(tenant.clone()), &Some(realm.clone()))
+        .await
+        .map_err(|e| {
+            BiError::StringError(format!("Failed to validate client credentials: {}", e))
+        })?;
+
+    save_tenant_and_realm(db, &tenant, &realm).await?;
+
+    println!("Provisioned tenant {} realm {}", tenant_id, realm_id);
+
+    Ok((tenant, realm))
+}
+
+// Check for existing tenant-realm combination to avoid duplicates
+async fn ensure_not_provisioned(
+    db: &Database,
+    tenant_id: &str,
+    realm_id: &str,
+) -> Result<(), BiError> {
     let tenants_with_realms = db.get_all_tenants_with_realms().await?;
 
-    // Check for existing tenant-realm combination to avoid duplicates
     if tenants_with_realms
         .iter()
         .any(|(t, realms)| t.id == tenant_id && realms.iter().any(|r| r.id == realm_id))
@@ -120,20 +209,129 @@ pub async fn provision_tenant(
             "Tenant/realm already provisioned".to_string(),
         ));
     }
+    Ok(())
+}
 
+// Store the tenant/realm, making it the default if none is set yet
+async fn save_tenant_and_realm(
+    db: &Database,
+    tenant: &database::models::Tenant,
+    realm: &database::models::Realm,
+) -> Result<(), BiError> {
     db.set_tenant_and_realm(tenant.clone(), realm.clone())
         .await?;
 
     // Check if there is already a default tenant and realm
     if db.get_default_tenant_and_realm().await?.is_none() {
         // Set this tenant and realm as the default
-        db.set_default_tenant_and_realm(&tenant_id, &realm_id)
+        db.set_default_tenant_and_realm(&tenant.id, &realm.id)
             .await?;
     }
+    Ok(())
+}
 
-    _ = display(db).await?;
+// Decode the JWT without any signature verification and return its issuer
+fn decode_issuer_unverified(token: &str) -> Result<String, BiError> {
+    let mut validation = Validation::new(TOKEN_ALGORITHM);
+    validation.insecure_disable_signature_validation();
+    validation.validate_aud = false;
 
-    Ok((tenant.clone(), realm.clone()))
+    let decoded_token = decode::<Claims>(
+        token,
+        &DecodingKey::from_secret("".as_ref()), // A dummy secret since we are disabling signature verification
+        &validation,
+    )
+    .map_err(|err| BiError::StringError(format!("Failed to decode JWT: {:?}", err)))?;
+
+    Ok(decoded_token.claims.iss)
+}
+
+// Verify the JWT against the JWKS published by its issuer and return the
+// issuer. The issuer must be served over https from a Beyond Identity domain,
+// so a token cannot point the CLI at an arbitrary host.
+async fn verify_token(client: &Client, token: &str) -> Result<String, BiError> {
+    let issuer_url = decode_issuer_unverified(token)?;
+    let parsed_issuer = Url::parse(&issuer_url).map_err(BiError::InvalidUrl)?;
+    if parsed_issuer.scheme() != "https" || !is_trusted_host(parsed_issuer.host_str()) {
+        return Err(BiError::StringError(format!(
+            "Untrusted token issuer {}. Use --insecure-skip-verify for local development.",
+            issuer_url
+        )));
+    }
+
+    let open_id_configuration: OpenIdConfiguration = fetch_json(
+        client,
+        &format!("{}/.well-known/openid-configuration", issuer_url),
+    )
+    .await?;
+    if open_id_configuration.issuer != issuer_url {
+        return Err(BiError::StringError(format!(
+            "Issuer mismatch: token was issued by {} but the OpenID configuration names {}",
+            issuer_url, open_id_configuration.issuer
+        )));
+    }
+    let jwks_url = Url::parse(&open_id_configuration.jwks_uri).map_err(BiError::InvalidUrl)?;
+    if jwks_url.origin() != parsed_issuer.origin() {
+        return Err(BiError::StringError(format!(
+            "JWKS {} is not served by the token issuer",
+            jwks_url
+        )));
+    }
+    let jwks: JwkSet = fetch_json(client, jwks_url.as_str()).await?;
+
+    let header = decode_header(token)
+        .map_err(|err| BiError::StringError(format!("Failed to decode JWT header: {:?}", err)))?;
+    let jwk = match &header.kid {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }
+    .ok_or(BiError::StringError(
+        "No key in the issuer's JWKS matches the token".to_string(),
+    ))?;
+    // The algorithm comes from the key, never from the unverified token header
+    if !matches!(jwk.algorithm, AlgorithmParameters::RSA(_))
+        || jwk
+            .common
+            .key_algorithm
+            .is_some_and(|alg| alg != KeyAlgorithm::RS256)
+    {
+        return Err(BiError::StringError(
+            "The issuer's signing key is not an RS256 key".to_string(),
+        ));
+    }
+    let decoding_key = DecodingKey::from_jwk(jwk)
+        .map_err(|err| BiError::StringError(format!("Invalid JWK: {:?}", err)))?;
+
+    let mut validation = Validation::new(TOKEN_ALGORITHM);
+    validation.validate_aud = false;
+    validation.set_issuer(&[&issuer_url]);
+    validation.set_required_spec_claims(&["exp", "iss"]);
+
+    decode::<Claims>(token, &decoding_key, &validation)
+        .map_err(|err| BiError::StringError(format!("Failed to verify JWT: {:?}", err)))?;
+
+    Ok(issuer_url)
+}
+
+fn is_trusted_host(host: Option<&str>) -> bool {
+    host.is_some_and(|host| {
+        TRUSTED_ISSUER_DOMAINS
+            .iter()
+            .any(|domain| host == *domain || host.ends_with(&format!(".{}", domain)))
+    })
+}
+
+async fn fetch_json<T: serde::de::DeserializeOwned>(
+    client: &Client,
+    url: &str,
+) -> Result<T, BiError> {
+    let response = client.get(url).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(BiError::RequestError(status, response.text().await?));
+    }
+    Ok(response.json::<T>().await?)
 }
 
 pub async fn list_tenants_ui(db: &Database) -> Result<(), BiError> {
@@ -0,0 +1,79 @@
+use super::mock_server::{self, MockServerOptions};
+
+use crate::common::command::ambassador_impl_Executable;
+use crate::common::{command::Executable, error::BiError};
+
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+use std::time::Duration;
+
+// ====================================
+// Dev Commands
+// ====================================
+
+/// Tools for developing and testing the CLI without a real Beyond Identity tenant.
+#[derive(Subcommand, ambassador::Delegate)]
+#[delegate(Executable)]
+pub enum DevCommands {
+    /// Run a stateful, in-memory mock of the Beyond Identity API on localhost.
+    /// Any tenant/realm provisioned against it with client credentials is
+    /// created on the first token request.
+    ///
+    /// Example:
+    ///
+    ///   bi dev mock-server --port 8023
+    ///
+    ///   bi config tenants add --tenant-id t --realm-id r --application-id a
+    ///     --client-id c --client-secret s --api-base-url http://localhost:8023
+    ///     --auth-base-url http://localhost:8021
+    MockServer(MockServer),
+}
+
+// ====================================
+// Dev Mock Server
+// ====================================
+
+#[derive(Args)]
+pub struct MockServer {
+    /// Port serving the API
+    #[clap(long, default_value_t = 8023)]
+    port: u16,
+
+    /// Port serving the token endpoint. Provisioning derives it from the API port,
+    /// mapping localhost:8023 to localhost:8021.
+    #[clap(long, default_value_t = 8021)]
+    auth_port: u16,
+
+    /// Largest page returned by list endpoints, whatever page_size is requested
+    #[clap(long, default_value_t = 100)]
+    max_page_size: usize,
+
+    /// Answer every Nth API request with 429 Too Many Requests. 0 disables throttling.
+    #[clap(long, default_value_t = 0)]
+    throttle_every: usize,
+
+    /// Seconds sent in the Retry-After header of throttled responses
+    #[clap(long, default_value_t = 1)]
+    retry_after: u64,
+
+    /// Lifetime of issued access tokens in seconds
+    #[clap(long, default_value_t = 3600)]
+    token_ttl: u64,
+}
+
+#[async_trait]
+impl Executable for MockServer {
+    async fn execute(&self) -> Result<(), BiError> {
+        mock_server::run(
+            self.port,
+            self.auth_port,
+            MockServerOptions {
+                max_page_size: self.max_page_size.max(1),
+                throttle_every: self.throttle_every,
+                retry_after: Duration::from_secs(self.retry_after),
+                token_ttl: Duration::from_secs(self.token_ttl),
+            },
+        )
+        .await
+    }
+}
@@ -0,0 +1,829 @@
+use crate::common::error::BiError;
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router};
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use chrono::{SecondsFormat, Utc};
+use log::info;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Behaviour of the mock server that tests may want to vary.
+#[derive(Debug, Clone)]
+pub struct MockServerOptions {
+    /// Largest page returned by list endpoints
+    pub max_page_size: usize,
+    /// Answer every Nth API request with 429. 0 disables throttling.
+    pub throttle_every: usize,
+    /// Delay sent in the Retry-After header of throttled responses
+    pub retry_after: Duration,
+    /// Lifetime of issued access tokens
+    pub token_ttl: Duration,
+}
+
+/// Serve the mock API on `port` and its token endpoint on `auth_port` until
+/// the process is stopped. Both ports share the same state.
+pub async fn run(port: u16, auth_port: u16, options: MockServerOptions) -> Result<(), BiError> {
+    let state = Arc::new(MockState {
+        options,
+        auth_port,
+        store: Mutex::new(Store::default()),
+        tokens: Mutex::new(HashMap::new()),
+        requests: AtomicUsize::new(0),
+    });
+    let app = Router::new().fallback(handle).with_state(state);
+
+    let api = bind(port).await?;
+    info!(
+        "Mock Beyond Identity API listening on http://localhost:{}",
+        port
+    );
+    if auth_port == port {
+        return serve(api, app).await;
+    }
+    let auth = bind(auth_port).await?;
+    info!(
+        "Mock Beyond Identity auth listening on http://localhost:{}",
+        auth_port
+    );
+    tokio::try_join!(serve(api, app.clone()), serve(auth, app)).map(|_| ())
+}
+
+async fn bind(port: u16) -> Result<tokio::net::TcpListener, BiError> {
+    tokio::net::TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|e| BiError::StringError(format!("Failed to bind port {}: {}", port, e)))
+}
+
+async fn serve(listener: tokio::net::TcpListener, app: Router) -> Result<(), BiError> {
+    axum::serve(listener, app)
+        .await
+        .map_err(|e| BiError::StringError(e.to_string()))
+}
+
+// ====================================
+// State
+// ====================================
+
+struct MockState {
+    options: MockServerOptions,
+    auth_port: u16,
+    store: Mutex<Store>,
+    // Issued access tokens with the tenant they were issued for and their expiry
+    tokens: Mutex<HashMap<String, (String, i64)>>,
+    requests: AtomicUsize,
+}
+
+#[derive(Default)]
+struct Store {
+    tenants: BTreeMap<String, TenantState>,
+}
+
+struct TenantState {
+    tenant: Value,
+    realms: Vec<RealmState>,
+}
+
+struct RealmState {
+    realm: Value,
+    identities: Vec<Value>,
+    groups: Vec<Value>,
+    // (group ID, identity ID) pairs
+    members: Vec<(String, String)>,
+    credentials: Vec<Value>,
+    credential_binding_jobs: Vec<Value>,
+}
+
+impl Store {
+    // Tenants and realms come into existence the first time a token is
+    // requested for them, so any provisioned IDs work.
+    fn ensure_realm(&mut self, tenant_id: &str, realm_id: &str) {
+        let tenant = self
+            .tenants
+            .entry(tenant_id.to_string())
+            .or_insert_with(|| TenantState {
+                tenant: json!({
+                    "id": tenant_id,
+                    "display_name": "Mock Tenant",
+                    "create_time": now(),
+                    "update_time": now(),
+                }),
+                realms: Vec::new(),
+            });
+        if !tenant.realms.iter().any(|r| id_of(&r.realm) == realm_id) {
+            tenant.realms.push(RealmState::new(json!({
+                "id": realm_id,
+                "tenant_id": tenant_id,
+                "display_name": "Mock Realm",
+                "classification": "SECURE_CUSTOMER",
+                "create_time": now(),
+                "update_time": now(),
+            })));
+        }
+    }
+
+    fn tenant_mut(&mut self, tenant_id: &str) -> Result<&mut TenantState, MockError> {
+        self.tenants
+            .get_mut(tenant_id)
+            .ok_or_else(|| not_found("tenant", tenant_id))
+    }
+}
+
+impl TenantState {
+    fn realm_mut(&mut self, realm_id: &str) -> Result<&mut RealmState, MockError> {
+        self.realms
+            .iter_mut()
+            .find(|r| id_of(&r.realm) == realm_id)
+            .ok_or_else(|| not_found("realm", realm_id))
+    }
+}
+
+impl RealmState {
+    fn new(realm: Value) -> Self {
+        Self {
+            realm,
+            identities: Vec::new(),
+            groups: Vec::new(),
+            members: Vec::new(),
+            credentials: Vec::new(),
+            credential_binding_jobs: Vec::new(),
+        }
+    }
+
+    fn tenant_and_realm_ids(&self) -> (String, String) {
+        (
+            self.realm["tenant_id"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            id_of(&self.realm).to_string(),
+        )
+    }
+}
+
+// ====================================
+// Requests
+// ====================================
+
+type MockError = (StatusCode, String);
+type MockResult = Result<Value, MockError>;
+
+struct MockRequest<'a> {
+    method: &'a Method,
+    query: BTreeMap<String, String>,
+    body: Value,
+}
+
+async fn handle(
+    State(state): State<Arc<MockState>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let response = state.respond(&method, &uri, &headers, &body);
+    info!("{} {} -> {}", method, uri, response.status());
+    response
+}
+
+impl MockState {
+    fn respond(&self, method: &Method, uri: &Uri, headers: &HeaderMap, body: &Bytes) -> Response {
+        // Custom methods are appended to the last segment, e.g. /groups/{id}:addMembers
+        let mut segments: Vec<&str> = uri.path().trim_matches('/').split('/').collect();
+        let mut custom = None;
+        if let Some(last) = segments.last_mut() {
+            if let Some((segment, method)) = last.split_once(':') {
+                *last = segment;
+                custom = Some(method);
+            }
+        }
+
+        if let (
+            "POST",
+            ["v1", "tenants", tenant_id, "realms", realm_id, "applications", _, "token"],
+        ) = (method.as_str(), segments.as_slice())
+        {
+            return match self.issue_token(tenant_id, realm_id, headers, body) {
+                Ok(token) => (StatusCode::OK, Json(token)).into_response(),
+                Err((status, message)) => (
+                    status,
+                    Json(json!({ "error": "invalid_client", "error_description": message })),
+                )
+                    .into_response(),
+            };
+        }
+
+        let count = self.requests.fetch_add(1, Ordering::SeqCst) + 1;
+        if count.checked_rem(self.options.throttle_every) == Some(0) {
+            let mut response = error_response((
+                StatusCode::TOO_MANY_REQUESTS,
+                "Rate limit exceeded".to_string(),
+            ));
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                self.options.retry_after.as_secs().into(),
+            );
+            return response;
+        }
+
+        let result = self.authorize(&segments, headers).and_then(|_| {
+            let request = MockRequest {
+                method,
+                query: url::form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes())
+                    .into_owned()
+                    .collect(),
+                body: if body.is_empty() {
+                    Value::Null
+                } else {
+                    serde_json::from_slice(body).map_err(|e| {
+                        (StatusCode::BAD_REQUEST, format!("Invalid JSON body: {}", e))
+                    })?
+                },
+            };
+            let mut store = self.store.lock().map_err(internal_error)?;
+            self.route(&mut store, &segments, custom, &request)
+        });
+
+        match result {
+            Ok(value) => (StatusCode::OK, Json(value)).into_response(),
+            Err(error) => error_response(error),
+        }
+    }
+
+    fn issue_token(
+        &self,
+        tenant_id: &str,
+        realm_id: &str,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> MockResult {
+        let client_id = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Basic "))
+            .and_then(|credentials| STANDARD.decode(credentials).ok())
+            .and_then(|credentials| String::from_utf8(credentials).ok())
+            .and_then(|credentials| Some(credentials.split_once(':')?.0.to_string()))
+            .ok_or((
+                StatusCode::UNAUTHORIZED,
+                "Missing client credentials".to_string(),
+            ))?;
+        let grant_type = url::form_urlencoded::parse(body)
+            .find(|(key, _)| key == "grant_type")
+            .map(|(_, value)| value.into_owned());
+        if grant_type.as_deref() != Some("client_credentials") {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Only the client_credentials grant is supported".to_string(),
+            ));
+        }
+
+        self.store
+            .lock()
+            .map_err(internal_error)?
+            .ensure_realm(tenant_id, realm_id);
+
+        // An unsigned JWT, so the claims can be inspected like a real token
+        let issued_at = Utc::now().timestamp();
+        let expires_in = self.options.token_ttl.as_secs();
+        let claims = json!({
+            "iss": format!("http://localhost:{}/v1/tenants/{}/realms/{}", self.auth_port, tenant_id, realm_id),
+            "sub": client_id,
+            "aud": "beyondidentity",
+            "iat": issued_at,
+            "exp": issued_at + expires_in as i64,
+            "jti": new_id(),
+            "scope": "mock",
+            "bi_t": tenant_id,
+            "bi_r": realm_id,
+        });
+        let access_token = format!(
+            "{}.{}.mock",
+            URL_SAFE_NO_PAD.encode(json!({ "alg": "none", "typ": "JWT" }).to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string()),
+        );
+        self.tokens.lock().map_err(internal_error)?.insert(
+            access_token.clone(),
+            (tenant_id.to_string(), issued_at + expires_in as i64),
+        );
+
+        Ok(json!({
+            "access_token": access_token,
+            "token_type": "Bearer",
+            "expires_in": expires_in,
+            "scope": "mock",
+        }))
+    }
+
+    // Every API request needs an unexpired token issued for the tenant in the path
+    fn authorize(&self, segments: &[&str], headers: &HeaderMap) -> Result<(), MockError> {
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or((StatusCode::UNAUTHORIZED, "Missing bearer token".to_string()))?;
+        let tokens = self.tokens.lock().map_err(internal_error)?;
+        let (tenant_id, expires_at) = tokens
+            .get(token)
+            .ok_or((StatusCode::UNAUTHORIZED, "Unknown token".to_string()))?;
+        if *expires_at <= Utc::now().timestamp() {
+            return Err((StatusCode::UNAUTHORIZED, "Token expired".to_string()));
+        }
+        match segments {
+            ["v1", "tenants", id, ..] if id != tenant_id => Err((
+                StatusCode::FORBIDDEN,
+                format!("Token was not issued for tenant {}", id),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    // ====================================
+    // Tenants and Realms
+    // ====================================
+
+    fn route(
+        &self,
+        store: &mut Store,
+        segments: &[&str],
+        custom: Option<&str>,
+        request: &MockRequest,
+    ) -> MockResult {
+        match (request.method.as_str(), segments, custom) {
+            ("GET", ["v1", "tenants", tenant_id], None) => {
+                Ok(store.tenant_mut(tenant_id)?.tenant.clone())
+            }
+            ("PATCH", ["v1", "tenants", tenant_id], None) => {
+                let tenant = store.tenant_mut(tenant_id)?;
+                patch(&mut tenant.tenant, &request.body["tenant"]);
+                Ok(tenant.tenant.clone())
+            }
+            ("GET", ["v1", "tenants", tenant_id, "realms"], None) => {
+                let realms = store
+                    .tenant_mut(tenant_id)?
+                    .realms
+                    .iter()
+                    .map(|r| r.realm.clone())
+                    .collect();
+                self.page("realms", realms, &request.query)
+            }
+            ("POST", ["v1", "tenants", tenant_id, "realms"], None) => {
+                let tenant = store.tenant_mut(tenant_id)?;
+                let body = &request.body["realm"];
+                let realm = json!({
+                    "id": new_id(),
+                    "tenant_id": tenant_id,
+                    "display_name": required_str(body, "display_name")?,
+                    "classification": body["classification"],
+                    "create_time": now(),
+                    "update_time": now(),
+                });
+                tenant.realms.push(RealmState::new(realm.clone()));
+                Ok(realm)
+            }
+            ("GET", ["v1", "tenants", tenant_id, "realms", realm_id], None) => Ok(store
+                .tenant_mut(tenant_id)?
+                .realm_mut(realm_id)?
+                .realm
+                .clone()),
+            ("PATCH", ["v1", "tenants", tenant_id, "realms", realm_id], None) => {
+                let realm = store.tenant_mut(tenant_id)?.realm_mut(realm_id)?;
+                patch(&mut realm.realm, &request.body["realm"]);
+                Ok(realm.realm.clone())
+            }
+            ("DELETE", ["v1", "tenants", tenant_id, "realms", realm_id], None) => {
+                let tenant = store.tenant_mut(tenant_id)?;
+                tenant.realm_mut(realm_id)?;
+                tenant.realms.retain(|r| id_of(&r.realm) != *realm_id);
+                Ok(json!({}))
+            }
+            (_, ["v1", "tenants", tenant_id, "realms", realm_id, rest @ ..], _) => {
+                let realm = store.tenant_mut(tenant_id)?.realm_mut(realm_id)?;
+                self.route_realm(realm, rest, custom, request)
+            }
+            _ => Err(not_found("route", &segments.join("/"))),
+        }
+    }
+
+    // ====================================
+    // Realm Resources
+    // ====================================
+
+    fn route_realm(
+        &self,
+        realm: &mut RealmState,
+        segments: &[&str],
+        custom: Option<&str>,
+        request: &MockRequest,
+    ) -> MockResult {
+        let (tenant_id, realm_id) = realm.tenant_and_realm_ids();
+        let body = &request.body;
+
+        match (request.method.as_str(), segments, custom) {
+            // Identities
+            ("GET", ["identities"], None) => {
+                self.page("identities", realm.identities.clone(), &request.query)
+            }
+            ("POST", ["identities"], None) => {
+                let body = &body["identity"];
+                let username = required_str(&body["traits"], "username")?;
+                if realm
+                    .identities
+                    .iter()
+                    .any(|i| i["traits"]["username"] == username)
+                {
+                    return Err((
+                        StatusCode::CONFLICT,
+                        format!("Identity with username {} already exists", username),
+                    ));
+                }
+                let identity = json!({
+                    "id": new_id(),
+                    "realm_id": realm_id,
+                    "tenant_id": tenant_id,
+                    "display_name": required_str(body, "display_name")?,
+                    "status": body.get("status").cloned().unwrap_or(json!("active")),
+                    "create_time": now(),
+                    "update_time": now(),
+                    "traits": body["traits"],
+                });
+                realm.identities.push(identity.clone());
+                Ok(identity)
+            }
+            ("GET", ["identities", identity_id], None) => {
+                Ok(find(&mut realm.identities, "identity", identity_id)?.clone())
+            }
+            ("PATCH", ["identities", identity_id], None) => {
+                let identity = find(&mut realm.identities, "identity", identity_id)?;
+                patch(identity, &body["identity"]);
+                Ok(identity.clone())
+            }
+            ("DELETE", ["identities", identity_id], None) => {
+                find(&mut realm.identities, "identity", identity_id)?;
+                realm.identities.retain(|i| id_of(i) != *identity_id);
+                realm.members.retain(|(_, member)| member != identity_id);
+                realm
+                    .credentials
+                    .retain(|c| c["identity_id"] != *identity_id);
+                realm
+                    .credential_binding_jobs
+                    .retain(|j| j["identity_id"] != *identity_id);
+                Ok(json!({}))
+            }
+            ("GET", ["identities", identity_id], Some("listGroups")) => {
+                find(&mut realm.identities, "identity", identity_id)?;
+                let groups = realm
+                    .groups
+                    .iter()
+                    .filter(|g| {
+                        realm
+                            .members
+                            .iter()
+                            .any(|(group, member)| group == id_of(g) && member == identity_id)
+                    })
+                    .cloned()
+                    .collect();
+                self.page("groups", groups, &request.query)
+            }
+            ("GET", ["identities", identity_id], Some("listRoles")) => {
+                find(&mut realm.identities, "identity", identity_id)?;
+                self.page("roles", Vec::new(), &request.query)
+            }
+
+            // Credentials
+            ("GET", ["identities", identity_id, "credentials"], None) => {
+                find(&mut realm.identities, "identity", identity_id)?;
+                let credentials = realm
+                    .credentials
+                    .iter()
+                    .filter(|c| c["identity_id"] == *identity_id)
+                    .cloned()
+                    .collect();
+                self.page("credentials", credentials, &request.query)
+            }
+            ("GET", ["identities", identity_id, "credentials", credential_id], None) => {
+                Ok(find_owned(
+                    &mut realm.credentials,
+                    "credential",
+                    credential_id,
+                    identity_id,
+                )?
+                .clone())
+            }
+            ("POST", ["identities", identity_id, "credentials", credential_id], Some("revoke")) => {
+                let credential = find_owned(
+                    &mut realm.credentials,
+                    "credential",
+                    credential_id,
+                    identity_id,
+                )?;
+                credential["state"] = json!("REVOKED");
+                credential["update_time"] = json!(now());
+                Ok(credential.clone())
+            }
+
+            // Credential binding jobs complete immediately, binding a new
+            // active credential so enrollment flows can be exercised.
+            ("POST", ["identities", identity_id, "credential-binding-jobs"], None) => {
+                find(&mut realm.identities, "identity", identity_id)?;
+                let body = &body["job"];
+                let delivery_method = required_str(body, "delivery_method")?;
+                let job_id = new_id();
+                let job = json!({
+                    "id": job_id,
+                    "realm_id": realm_id,
+                    "tenant_id": tenant_id,
+                    "identity_id": identity_id,
+                    "delivery_method": delivery_method,
+                    "state": "COMPLETE",
+                    "post_binding_redirect_uri": body["post_binding_redirect_uri"],
+                    "authenticator_config": body["authenticator_config"],
+                    "authenticator_config_id": body["authenticator_config_id"],
+                    "expire_time": (Utc::now() + chrono::Duration::days(7))
+                        .to_rfc3339_opts(SecondsFormat::Millis, true),
+                    "create_time": now(),
+                    "update_time": now(),
+                });
+                realm.credential_binding_jobs.push(job.clone());
+                realm.credentials.push(json!({
+                    "id": new_id(),
+                    "realm_id": realm_id,
+                    "tenant_id": tenant_id,
+                    "identity_id": identity_id,
+                    "state": "ACTIVE",
+                    "csr_type": "JWT",
+                    "jwk_json": "{}",
+                    "jwk_thumbprint": new_id(),
+                    "create_time": now(),
+                    "update_time": now(),
+                }));
+                let link = (delivery_method == "RETURN").then(|| {
+                    format!(
+                        "http://localhost:{}/v1/tenants/{}/realms/{}/credential-binding-jobs/{}:bind",
+                        self.auth_port, tenant_id, realm_id, job_id
+                    )
+                });
+                Ok(json!({
+                    "credential_binding_job": job,
+                    "credential_binding_link": link,
+                }))
+            }
+            ("GET", ["identities", identity_id, "credential-binding-jobs"], None) => {
+                find(&mut realm.identities, "identity", identity_id)?;
+                let jobs = realm
+                    .credential_binding_jobs
+                    .iter()
+                    .filter(|j| j["identity_id"] == *identity_id)
+                    .cloned()
+                    .collect();
+                self.page("credential_binding_jobs", jobs, &request.query)
+            }
+            ("GET", ["identities", identity_id, "credential-binding-jobs", job_id], None) => {
+                Ok(find_owned(
+                    &mut realm.credential_binding_jobs,
+                    "credential binding job",
+                    job_id,
+                    identity_id,
+                )?
+                .clone())
+            }
+
+            // Groups
+            ("GET", ["groups"], None) => self.page("groups", realm.groups.clone(), &request.query),
+            ("POST", ["groups"], None) => {
+                let body = &body["group"];
+                let group = json!({
+                    "id": new_id(),
+                    "realm_id": realm_id,
+                    "tenant_id": tenant_id,
+                    "display_name": required_str(body, "display_name")?,
+                    "description": body["description"].as_str().unwrap_or_default(),
+                    "create_time": now(),
+                    "update_time": now(),
+                });
+                realm.groups.push(group.clone());
+                Ok(group)
+            }
+            ("GET", ["groups", group_id], None) => {
+                Ok(find(&mut realm.groups, "group", group_id)?.clone())
+            }
+            ("PATCH", ["groups", group_id], None) => {
+                let group = find(&mut realm.groups, "group", group_id)?;
+                patch(group, &body["group"]);
+                Ok(group.clone())
+            }
+            ("DELETE", ["groups", group_id], None) => {
+                find(&mut realm.groups, "group", group_id)?;
+                realm.groups.retain(|g| id_of(g) != *group_id);
+                realm.members.retain(|(group, _)| group != group_id);
+                Ok(json!({}))
+            }
+            ("POST", ["groups", group_id], Some("addMembers")) => {
+                find(&mut realm.groups, "group", group_id)?;
+                for identity_id in identity_ids(body)? {
+                    find(&mut realm.identities, "identity", &identity_id)?;
+                    let membership = (group_id.to_string(), identity_id);
+                    if !realm.members.contains(&membership) {
+                        realm.members.push(membership);
+                    }
+                }
+                Ok(find(&mut realm.groups, "group", group_id)?.clone())
+            }
+            ("POST", ["groups", group_id], Some("deleteMembers")) => {
+                find(&mut realm.groups, "group", group_id)?;
+                let identity_ids = identity_ids(body)?;
+                realm
+                    .members
+                    .retain(|(group, member)| group != group_id || !identity_ids.contains(member));
+                Ok(find(&mut realm.groups, "group", group_id)?.clone())
+            }
+            ("GET", ["groups", group_id], Some("listMembers")) => {
+                find(&mut realm.groups, "group", group_id)?;
+                let members = realm
+                    .identities
+                    .iter()
+                    .filter(|i| {
+                        realm
+                            .members
+                            .iter()
+                            .any(|(group, member)| group == group_id && member == id_of(i))
+                    })
+                    .cloned()
+                    .collect();
+                self.page("identities", members, &request.query)
+            }
+            ("GET", ["groups", group_id], Some("listRoles")) => {
+                find(&mut realm.groups, "group", group_id)?;
+                self.page("roles", Vec::new(), &request.query)
+            }
+
+            // Resource servers and roles are not modelled; listing them returns no items
+            ("GET", ["resource-servers"], None) => {
+                self.page("resource_servers", Vec::new(), &request.query)
+            }
+            ("GET", ["resource-servers", _, "roles"], None) => {
+                self.page("roles", Vec::new(), &request.query)
+            }
+
+            _ => Err(not_found("route", &segments.join("/"))),
+        }
+    }
+
+    // Filter and paginate a list. Page tokens are the offset of the next page.
+    fn page(&self, key: &str, items: Vec<Value>, query: &BTreeMap<String, String>) -> MockResult {
+        let items = match query.get("filter").filter(|f| !f.trim().is_empty()) {
+            Some(filter) => scim_filter::scim_filter(filter, items)
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid filter: {}", e)))?,
+            None => items,
+        };
+        let page_size = query
+            .get("page_size")
+            .and_then(|size| size.parse::<usize>().ok())
+            .filter(|size| *size > 0)
+            .unwrap_or(self.options.max_page_size)
+            .min(self.options.max_page_size);
+        let offset = match query.get("page_token") {
+            Some(token) => token
+                .parse::<usize>()
+                .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid page_token".to_string()))?,
+            None => 0,
+        };
+
+        let total_size = items.len();
+        let end = offset.saturating_add(page_size).min(total_size);
+        let mut response = Map::new();
+        response.insert(
+            key.to_string(),
+            Value::Array(items.into_iter().take(end).skip(offset).collect()),
+        );
+        response.insert("total_size".to_string(), json!(total_size));
+        if end < total_size {
+            response.insert("next_page_token".to_string(), json!(end.to_string()));
+        }
+        Ok(Value::Object(response))
+    }
+}
+
+// ====================================
+// Helpers
+// ====================================
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+// A random ID in UUID v4 format
+fn new_id() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn id_of(value: &Value) -> &str {
+    value["id"].as_str().unwrap_or_default()
+}
+
+fn find<'a>(items: &'a mut [Value], kind: &str, id: &str) -> Result<&'a mut Value, MockError> {
+    items
+        .iter_mut()
+        .find(|item| id_of(item) == id)
+        .ok_or_else(|| not_found(kind, id))
+}
+
+// Find a resource that must belong to the given identity
+fn find_owned<'a>(
+    items: &'a mut [Value],
+    kind: &str,
+    id: &str,
+    identity_id: &str,
+) -> Result<&'a mut Value, MockError> {
+    items
+        .iter_mut()
+        .find(|item| id_of(item) == id && item["identity_id"] == identity_id)
+        .ok_or_else(|| not_found(kind, id))
+}
+
+fn required_str<'a>(body: &'a Value, field: &str) -> Result<&'a str, MockError> {
+    body[field]
+        .as_str()
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("{} is required", field)))
+}
+
+fn identity_ids(body: &Value) -> Result<Vec<String>, MockError> {
+    body["identity_ids"]
+        .as_array()
+        .map(|ids| {
+            ids.iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                "identity_ids is required".to_string(),
+            )
+        })
+}
+
+// Merge the fields of a PATCH body into a resource
+fn patch(resource: &mut Value, changes: &Value) {
+    merge(resource, changes);
+    resource["update_time"] = json!(now());
+}
+
+fn merge(target: &mut Value, changes: &Value) {
+    match (target, changes) {
+        (Value::Object(target), Value::Object(changes)) => {
+            for (key, value) in changes {
+                if key == "id" {
+                    continue;
+                }
+                match target.get_mut(key) {
+                    Some(existing) if existing.is_object() => merge(existing, value),
+                    _ => {
+                        target.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (target, changes) if !changes.is_null() => *target = changes.clone(),
+        _ => {}
+    }
+}
+
+fn not_found(kind: &str, id: &str) -> MockError {
+    (StatusCode::NOT_FOUND, format!("{} {} not found", kind, id))
+}
+
+fn internal_error(e: impl std::fmt::Display) -> MockError {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+fn error_response((status, message): MockError) -> Response {
+    let code = status
+        .canonical_reason()
+        .unwrap_or("error")
+        .to_lowercase()
+        .replace(' ', "_");
+    (status, Json(json!({ "code": code, "message": message }))).into_response()
+}
@@ -0,0 +1,73 @@
+use super::fast_migrate;
+
+use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::beyond_identity::api::common::middleware::rate_limit::RespectRateLimitMiddleware;
+use crate::common::command::ambassador_impl_Executable;
+use crate::common::http;
+use crate::common::{command::Executable, error::BiError};
+
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+use reqwest_middleware::ClientBuilder;
+
+// ====================================
+// Entra Commands
+// ====================================
+
+/// Commands for facilitating migration from Microsoft Entra ID to Beyond Identity.
+#[derive(Subcommand, ambassador::Delegate)]
+#[delegate(Executable)]
+pub enum EntraCommands {
+    /// Automatically migrate Entra ID enterprise applications to Beyond Identity SSO and assign users based on existing app role assignments.
+    /// Each application tile in Beyond Identity will act as an opaque redirect to the Microsoft My Apps portal.
+    FastMigrate(FastMigrate),
+}
+
+// ====================================
+// Entra FastMigrate
+// ====================================
+
+#[derive(Args)]
+pub struct FastMigrate;
+
+#[async_trait]
+impl Executable for FastMigrate {
+    async fn execute(&self) -> Result<(), BiError> {
+        let http_client = http::client();
+        let entra_client = ClientBuilder::new(http_client.clone())
+            .with(RespectRateLimitMiddleware)
+            .build();
+
+        let api_client = ApiClient::new(None, None).await;
+        let entra_config = api_client.db.get_entra_config().await?.expect("Failed to load Entra Configuration. Make sure to setup Entra before running this command.");
+
+        let entra_applications =
+            fast_migrate::fetch_entra_applications(&entra_client, &entra_config)
+                .await
+                .expect("Failed to fetch entra applications");
+
+        let selected_applications = fast_migrate::select_applications(&entra_applications);
+        for app in selected_applications {
+            match fast_migrate::create_sso_config_and_assign_identities(
+                &api_client,
+                &entra_config,
+                &app,
+            )
+            .await
+            {
+                Ok(sso_config) => println!(
+                    "SSO config created for {}: {}",
+                    app.display_name,
+                    serde_json::to_string_pretty(&sso_config).unwrap()
+                ),
+                Err(err) => {
+                    println!(
+                        "Failed to create SSO config for {}: {}",
+                        app.display_name, err
+                    )
+                }
+            }
+        }
+        Ok(())
+    }
+}
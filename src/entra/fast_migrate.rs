@@ -0,0 +1,346 @@
+use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::beyond_identity::api::common::service::IdentitiesService;
+use crate::beyond_identity::api::identities::api::IdentitiesApi;
+use crate::beyond_identity::api::identities::types::Identity;
+use crate::beyond_identity::helper::sso_configs;
+use crate::common::database::models::EntraConfig;
+use crate::common::error::BiError;
+
+use reqwest_middleware::ClientWithMiddleware as Client;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntraApplication {
+    id: String,
+    app_id: String,
+    pub display_name: String,
+    #[serde(default)]
+    account_enabled: Option<bool>,
+    #[serde(default)]
+    info: Option<EntraApplicationInfo>,
+    #[serde(default, skip_deserializing)]
+    assigned_users: Vec<EntraUser>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EntraApplicationInfo {
+    logo_url: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EntraAppRoleAssignment {
+    principal_id: String,
+    principal_type: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntraUser {
+    id: String,
+    mail: Option<String>,
+    user_principal_name: Option<String>,
+}
+
+impl EntraUser {
+    // Entra users without a mailbox only carry a user principal name, which is
+    // usually the address they sign in with.
+    fn email(&self) -> Option<&str> {
+        self.mail.as_deref().or(self.user_principal_name.as_deref())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphPage<T> {
+    value: Vec<T>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+}
+
+async fn get_entra_access_token(
+    client: &Client,
+    entra_config: &EntraConfig,
+) -> Result<String, BiError> {
+    let url = format!(
+        "{}/{}/oauth2/v2.0/token",
+        entra_config.login_base_url, entra_config.tenant_id
+    );
+    let scope = format!("{}/.default", graph_origin(entra_config)?);
+
+    let response = client
+        .post(&url)
+        .form(&[
+            ("grant_type", "client_credentials"),
+            ("client_id", entra_config.client_id.as_str()),
+            ("client_secret", entra_config.client_secret.as_str()),
+            ("scope", scope.as_str()),
+        ])
+        .send()
+        .await?;
+
+    let status = response.status();
+    let response_text = response.text().await?;
+
+    log::debug!("{} response status: {}", url, status);
+
+    if !status.is_success() {
+        return Err(BiError::RequestError(status, response_text));
+    }
+
+    let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
+    let access_token = response_json
+        .get("access_token")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| BiError::StringError("Access token not found".to_string()))?;
+
+    Ok(access_token.to_string())
+}
+
+// The token scope is the Graph origin (e.g. https://graph.microsoft.com), not the
+// versioned base URL the requests are sent to.
+fn graph_origin(entra_config: &EntraConfig) -> Result<String, BiError> {
+    let parsed_url = url::Url::parse(&entra_config.graph_base_url).map_err(BiError::InvalidUrl)?;
+    Ok(parsed_url.origin().ascii_serialization())
+}
+
+// Fetches every page of a Microsoft Graph collection by following `@odata.nextLink`.
+async fn fetch_all_pages<T: DeserializeOwned>(
+    client: &Client,
+    url: &str,
+    access_token: &str,
+) -> Result<Vec<T>, BiError> {
+    let mut results = Vec::new();
+    let mut url = url.to_string();
+
+    loop {
+        let response = client.get(&url).bearer_auth(access_token).send().await?;
+
+        let status = response.status();
+        let response_text = response.text().await?;
+
+        log::debug!(
+            "{} response status: {} and text: {}",
+            url,
+            status,
+            response_text
+        );
+
+        if !status.is_success() {
+            return Err(BiError::RequestError(status, response_text));
+        }
+
+        let page: GraphPage<T> = serde_json::from_str(&response_text)?;
+        results.extend(page.value);
+
+        match page.next_link {
+            Some(next) => url = next,
+            None => break,
+        }
+    }
+
+    Ok(results)
+}
+
+async fn fetch_all_entra_users(
+    client: &Client,
+    entra_config: &EntraConfig,
+    access_token: &str,
+) -> Result<HashMap<String, EntraUser>, BiError> {
+    let url = format!(
+        "{}/users?$select=id,mail,userPrincipalName&$top=999",
+        entra_config.graph_base_url
+    );
+    let users: Vec<EntraUser> = fetch_all_pages(client, &url, access_token).await?;
+    Ok(users
+        .into_iter()
+        .map(|user| (user.id.clone(), user))
+        .collect())
+}
+
+pub async fn fetch_entra_applications(
+    client: &Client,
+    entra_config: &EntraConfig,
+) -> Result<Vec<EntraApplication>, BiError> {
+    let access_token = get_entra_access_token(client, entra_config).await?;
+
+    let users_map = fetch_all_entra_users(client, entra_config, &access_token).await?;
+
+    // Enterprise applications are the service principals tagged as integrated apps.
+    let url = format!(
+        "{}/servicePrincipals?$filter=tags/any(t:t eq 'WindowsAzureActiveDirectoryIntegratedApp')&$select=id,appId,displayName,accountEnabled,info&$top=999",
+        entra_config.graph_base_url
+    );
+    let mut applications: Vec<EntraApplication> =
+        fetch_all_pages(client, &url, &access_token).await?;
+
+    let mut group_members_cache: HashMap<String, Vec<EntraUser>> = HashMap::new();
+
+    for app in &mut applications {
+        log::info!("Fetching assigned users for app: {:?}", app.display_name);
+        app.assigned_users = get_users_assigned_to_app(
+            client,
+            entra_config,
+            &access_token,
+            &app.id,
+            &users_map,
+            &mut group_members_cache,
+        )
+        .await?;
+    }
+
+    Ok(applications)
+}
+
+async fn get_users_assigned_to_app(
+    client: &Client,
+    entra_config: &EntraConfig,
+    access_token: &str,
+    service_principal_id: &str,
+    users_map: &HashMap<String, EntraUser>,
+    group_members_cache: &mut HashMap<String, Vec<EntraUser>>,
+) -> Result<Vec<EntraUser>, BiError> {
+    let url = format!(
+        "{}/servicePrincipals/{}/appRoleAssignedTo?$top=999",
+        entra_config.graph_base_url, service_principal_id
+    );
+    let assignments: Vec<EntraAppRoleAssignment> =
+        fetch_all_pages(client, &url, access_token).await?;
+
+    let mut assigned_users: HashMap<String, EntraUser> = HashMap::new();
+
+    for assignment in assignments {
+        match assignment.principal_type.as_str() {
+            "User" => {
+                if let Some(user) = users_map.get(&assignment.principal_id) {
+                    assigned_users.insert(user.id.clone(), user.clone());
+                }
+            }
+            "Group" => {
+                if !group_members_cache.contains_key(&assignment.principal_id) {
+                    let url = format!(
+                        "{}/groups/{}/transitiveMembers/microsoft.graph.user?$select=id,mail,userPrincipalName&$top=999",
+                        entra_config.graph_base_url, assignment.principal_id
+                    );
+                    let members: Vec<EntraUser> =
+                        fetch_all_pages(client, &url, access_token).await?;
+                    group_members_cache.insert(assignment.principal_id.clone(), members);
+                }
+                for user in &group_members_cache[&assignment.principal_id] {
+                    assigned_users.insert(user.id.clone(), user.clone());
+                }
+            }
+            other => log::debug!(
+                "Skipping assignment of principal {} with type {}",
+                assignment.principal_id,
+                other
+            ),
+        }
+    }
+
+    println!(
+        "Fetched {} users for app {}",
+        assigned_users.len(),
+        service_principal_id
+    );
+
+    Ok(assigned_users.into_values().collect())
+}
+
+pub fn select_applications(applications: &[EntraApplication]) -> Vec<EntraApplication> {
+    println!("Select applications to fast migrate (comma separated indices or 'all' for all applications):");
+
+    for (index, app) in applications.iter().enumerate() {
+        println!(
+            "{}: {} - {} (enabled: {})",
+            index,
+            app.display_name,
+            app.app_id,
+            app.account_enabled.unwrap_or(true)
+        );
+    }
+
+    print!("Your selection: ");
+    io::stdout().flush().unwrap();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+    let input = input.trim();
+
+    if input == "all" {
+        return applications.to_vec();
+    }
+
+    let indices: Vec<usize> = input
+        .split(',')
+        .map(|s| s.trim().parse().unwrap())
+        .collect();
+
+    indices
+        .into_iter()
+        .map(|i| applications[i].clone())
+        .collect()
+}
+
+fn filter_identities(
+    entra_users: &[EntraUser],
+    beyond_identity_identities: &[Identity],
+) -> Vec<Identity> {
+    let entra_user_emails: Vec<&str> = entra_users.iter().filter_map(|user| user.email()).collect();
+    beyond_identity_identities
+        .iter()
+        .filter(|identity| {
+            identity
+                .traits
+                .primary_email_address
+                .as_deref()
+                .is_some_and(|email| entra_user_emails.contains(&email))
+        })
+        .cloned()
+        .collect()
+}
+
+// Entra launches enterprise applications through the My Apps portal.
+fn login_link(entra_config: &EntraConfig, entra_application: &EntraApplication) -> String {
+    format!(
+        "https://launcher.myapps.microsoft.com/api/signin/{}?tenantId={}",
+        entra_application.app_id, entra_config.tenant_id
+    )
+}
+
+pub async fn create_sso_config_and_assign_identities(
+    api_client: &ApiClient,
+    entra_config: &EntraConfig,
+    entra_application: &EntraApplication,
+) -> Result<sso_configs::SsoConfigBookmark, BiError> {
+    let icon_url = entra_application
+        .info
+        .as_ref()
+        .and_then(|info| info.logo_url.clone());
+    let sso_config = sso_configs::create_sso_config(
+        api_client,
+        entra_application.display_name.clone(),
+        login_link(entra_config, entra_application),
+        icon_url,
+    )
+    .await?;
+
+    let beyond_identity_identities = IdentitiesService::new()
+        .build()
+        .await
+        .list_identities(None, None)
+        .await?
+        .identities;
+    let filtered_identities = filter_identities(
+        &entra_application.assigned_users,
+        &beyond_identity_identities,
+    );
+
+    sso_configs::assign_identities_to_sso_config(api_client, &sso_config, &filtered_identities)
+        .await?;
+
+    Ok(sso_config)
+}
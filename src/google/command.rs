@@ -0,0 +1,98 @@
+use super::fast_migrate;
+
+use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::beyond_identity::api::common::middleware::rate_limit::RespectRateLimitMiddleware;
+use crate::beyond_identity::api::common::service::{GroupsService, IdentitiesService};
+use crate::beyond_identity::api::groups::api::GroupsApi;
+use crate::beyond_identity::api::identities::api::IdentitiesApi;
+use crate::common::command::ambassador_impl_Executable;
+use crate::common::http;
+use crate::common::{command::Executable, error::BiError};
+
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+use reqwest_middleware::ClientBuilder;
+
+// ====================================
+// Google Commands
+// ====================================
+
+/// Commands for facilitating migration from Google Workspace to Beyond Identity.
+#[derive(Subcommand, ambassador::Delegate)]
+#[delegate(Executable)]
+pub enum GoogleCommands {
+    /// Create a Beyond Identity identity for every Google Workspace user, then recreate the selected Google groups
+    /// as Beyond Identity groups with the same memberships. Existing identities are matched by email address.
+    FastMigrate(FastMigrate),
+}
+
+// ====================================
+// Google FastMigrate
+// ====================================
+
+#[derive(Args)]
+pub struct FastMigrate {
+    /// Also create identities for suspended Google Workspace users
+    #[clap(long)]
+    include_suspended: bool,
+}
+
+#[async_trait]
+impl Executable for FastMigrate {
+    async fn execute(&self) -> Result<(), BiError> {
+        let http_client = http::client();
+        let google_client = ClientBuilder::new(http_client.clone())
+            .with(RespectRateLimitMiddleware)
+            .build();
+
+        let api_client = ApiClient::new(None, None).await;
+        let google_config = api_client.db.get_google_config().await?.expect("Failed to load Google Configuration. Make sure to setup Google before running this command.");
+
+        let google_users = fast_migrate::fetch_google_users(&google_client, &google_config)
+            .await
+            .expect("Failed to fetch google users")
+            .into_iter()
+            .filter(|user| self.include_suspended || !user.suspended)
+            .collect::<Vec<_>>();
+
+        let identities_service = IdentitiesService::new().build().await;
+        let beyond_identity_identities = identities_service
+            .list_identities(None, None)
+            .await?
+            .identities;
+        let identities_by_email = fast_migrate::create_missing_identities(
+            &identities_service,
+            &google_users,
+            beyond_identity_identities,
+        )
+        .await?;
+
+        let google_groups = fast_migrate::fetch_google_groups(&google_client, &google_config)
+            .await
+            .expect("Failed to fetch google groups");
+        let selected_groups = fast_migrate::select_groups(&google_groups);
+
+        let groups_service = GroupsService::new().build().await;
+        let existing_groups = groups_service.list_groups(None, None).await?.groups;
+
+        for google_group in selected_groups {
+            match fast_migrate::create_group_and_assign_identities(
+                &groups_service,
+                &existing_groups,
+                &identities_by_email,
+                &google_group,
+            )
+            .await
+            {
+                Ok((group, member_count)) => println!(
+                    "Group {} ({}) migrated with {} members",
+                    group.display_name, group.id, member_count
+                ),
+                Err(err) => {
+                    println!("Failed to migrate group {}: {}", google_group.name, err)
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,355 @@
+use crate::beyond_identity::api::common::service::{GroupsService, IdentitiesService};
+use crate::beyond_identity::api::groups::types::Group;
+use crate::beyond_identity::api::identities::types::{
+    CreateIdentity, CreateIdentityRequest, Identity, Traits, Type,
+};
+use crate::beyond_identity::helper::groups::{add_members_in_batches, find_or_create_group};
+use crate::beyond_identity::helper::identities;
+use crate::common::database::models::GoogleConfig;
+use crate::common::error::BiError;
+
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use reqwest_middleware::ClientWithMiddleware as Client;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DIRECTORY_SCOPES: &str = "https://www.googleapis.com/auth/admin.directory.user.readonly https://www.googleapis.com/auth/admin.directory.group.readonly";
+
+#[derive(Debug, Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    #[serde(default = "default_token_uri")]
+    token_uri: String,
+}
+
+fn default_token_uri() -> String {
+    "https://oauth2.googleapis.com/token".to_string()
+}
+
+#[derive(Debug, Serialize)]
+struct ServiceAccountClaims<'a> {
+    iss: &'a str,
+    sub: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleUser {
+    id: String,
+    pub primary_email: String,
+    #[serde(default)]
+    name: Option<GoogleUserName>,
+    #[serde(default)]
+    pub suspended: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleUserName {
+    given_name: Option<String>,
+    family_name: Option<String>,
+    full_name: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleGroup {
+    id: String,
+    email: String,
+    pub name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default, skip_deserializing)]
+    members: Vec<GoogleMember>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleMember {
+    id: String,
+    email: Option<String>,
+    r#type: String,
+    #[serde(default)]
+    status: Option<String>,
+}
+
+async fn get_google_access_token(
+    client: &Client,
+    google_config: &GoogleConfig,
+) -> Result<String, BiError> {
+    let key: ServiceAccountKey = serde_json::from_str(&google_config.service_account_key)?;
+
+    let iat = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let claims = ServiceAccountClaims {
+        iss: &key.client_email,
+        sub: &google_config.admin_email,
+        scope: DIRECTORY_SCOPES,
+        aud: &key.token_uri,
+        iat,
+        exp: iat + 3600,
+    };
+    let encoding_key = EncodingKey::from_rsa_pem(key.private_key.as_bytes())
+        .map_err(|e| BiError::StringError(format!("Invalid service account key: {}", e)))?;
+    let assertion = encode(&Header::new(Algorithm::RS256), &claims, &encoding_key)
+        .map_err(|e| BiError::StringError(format!("Failed to sign assertion: {}", e)))?;
+
+    let response = client
+        .post(&key.token_uri)
+        .form(&[
+            ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+            ("assertion", assertion.as_str()),
+        ])
+        .send()
+        .await?;
+
+    let status = response.status();
+    let response_text = response.text().await?;
+
+    log::debug!("{} response status: {}", key.token_uri, status);
+
+    if !status.is_success() {
+        return Err(BiError::RequestError(status, response_text));
+    }
+
+    let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
+    let access_token = response_json
+        .get("access_token")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| BiError::StringError("Access token not found".to_string()))?;
+
+    Ok(access_token.to_string())
+}
+
+// Fetches every page of a Directory API collection by following `nextPageToken`.
+// The items live under a collection specific key such as "users" or "groups".
+async fn fetch_all_pages<T: DeserializeOwned>(
+    client: &Client,
+    url: &str,
+    items_key: &str,
+    access_token: &str,
+) -> Result<Vec<T>, BiError> {
+    let mut results = Vec::new();
+    let mut page_token: Option<String> = None;
+
+    loop {
+        let mut request = client
+            .get(url)
+            .bearer_auth(access_token)
+            .query(&[("maxResults", "200")]);
+
+        if let Some(ref token) = page_token {
+            request = request.query(&[("pageToken", token)]);
+        }
+
+        let response = request.send().await?;
+
+        let status = response.status();
+        let response_text = response.text().await?;
+
+        log::debug!(
+            "{} response status: {} and text: {}",
+            url,
+            status,
+            response_text
+        );
+
+        if !status.is_success() {
+            return Err(BiError::RequestError(status, response_text));
+        }
+
+        let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
+        // Empty collections omit the items key entirely.
+        if let Some(items) = response_json.get(items_key) {
+            let page: Vec<T> = serde_json::from_value(items.clone())?;
+            results.extend(page);
+        }
+
+        match response_json
+            .get("nextPageToken")
+            .and_then(|token| token.as_str())
+        {
+            Some(token) => page_token = Some(token.to_string()),
+            None => break,
+        }
+    }
+
+    Ok(results)
+}
+
+pub async fn fetch_google_users(
+    client: &Client,
+    google_config: &GoogleConfig,
+) -> Result<Vec<GoogleUser>, BiError> {
+    let access_token = get_google_access_token(client, google_config).await?;
+    let url = format!(
+        "{}/admin/directory/v1/users?customer={}",
+        google_config.api_base_url, google_config.customer_id
+    );
+    let users: Vec<GoogleUser> = fetch_all_pages(client, &url, "users", &access_token).await?;
+    println!("Fetched {} Google Workspace users", users.len());
+    Ok(users)
+}
+
+pub async fn fetch_google_groups(
+    client: &Client,
+    google_config: &GoogleConfig,
+) -> Result<Vec<GoogleGroup>, BiError> {
+    let access_token = get_google_access_token(client, google_config).await?;
+    let url = format!(
+        "{}/admin/directory/v1/groups?customer={}",
+        google_config.api_base_url, google_config.customer_id
+    );
+    let mut groups: Vec<GoogleGroup> =
+        fetch_all_pages(client, &url, "groups", &access_token).await?;
+
+    for group in &mut groups {
+        log::info!("Fetching members for group: {:?}", group.name);
+        // Derived membership flattens nested groups into their user members.
+        let url = format!(
+            "{}/admin/directory/v1/groups/{}/members?includeDerivedMembership=true",
+            google_config.api_base_url, group.id
+        );
+        group.members = fetch_all_pages(client, &url, "members", &access_token).await?;
+        println!(
+            "Fetched {} members for group {}",
+            group.members.len(),
+            group.email
+        );
+    }
+
+    Ok(groups)
+}
+
+pub fn select_groups(groups: &[GoogleGroup]) -> Vec<GoogleGroup> {
+    println!("Select groups to migrate (comma separated indices, 'all' for all groups or 'none' to skip):");
+
+    for (index, group) in groups.iter().enumerate() {
+        println!(
+            "{}: {} - {} ({} members)",
+            index,
+            group.name,
+            group.email,
+            group.members.len()
+        );
+    }
+
+    print!("Your selection: ");
+    io::stdout().flush().unwrap();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+    let input = input.trim();
+
+    if input == "all" {
+        return groups.to_vec();
+    }
+
+    if input == "none" || input.is_empty() {
+        return vec![];
+    }
+
+    let indices: Vec<usize> = input
+        .split(',')
+        .map(|s| s.trim().parse().unwrap())
+        .collect();
+
+    indices.into_iter().map(|i| groups[i].clone()).collect()
+}
+
+fn create_identity_request(user: &GoogleUser) -> CreateIdentityRequest {
+    let name = user.name.as_ref();
+    CreateIdentityRequest {
+        identity: CreateIdentity {
+            display_name: name
+                .and_then(|name| name.full_name.clone())
+                .unwrap_or_else(|| user.primary_email.clone()),
+            traits: Traits {
+                r#type: Type::TraitsV0,
+                username: user.primary_email.clone(),
+                primary_email_address: Some(user.primary_email.clone()),
+                external_id: Some(user.id.clone()),
+                family_name: name.and_then(|name| name.family_name.clone()),
+                given_name: name.and_then(|name| name.given_name.clone()),
+            },
+        },
+    }
+}
+
+// Creates a Beyond Identity identity for every Google user that does not have one yet.
+// Returns a map of lowercase email to identity covering both the existing and the
+// newly created identities.
+pub async fn create_missing_identities(
+    identities_service: &IdentitiesService,
+    google_users: &[GoogleUser],
+    beyond_identity_identities: Vec<Identity>,
+) -> Result<HashMap<String, Identity>, BiError> {
+    let requests: Vec<CreateIdentityRequest> =
+        google_users.iter().map(create_identity_request).collect();
+
+    let identities = identities::create_missing_identities(
+        identities_service,
+        &requests,
+        beyond_identity_identities,
+    )
+    .await?;
+
+    Ok(identities
+        .into_iter()
+        .filter_map(|identity| {
+            identity
+                .traits
+                .primary_email_address
+                .clone()
+                .map(|email| (email.to_lowercase(), identity))
+        })
+        .collect())
+}
+
+pub async fn create_group_and_assign_identities(
+    groups_service: &GroupsService,
+    existing_groups: &[Group],
+    identities_by_email: &HashMap<String, Identity>,
+    google_group: &GoogleGroup,
+) -> Result<(Group, usize), BiError> {
+    let description = google_group
+        .description
+        .clone()
+        .filter(|description| !description.trim().is_empty())
+        .unwrap_or_else(|| {
+            format!(
+                "Migrated from Google Workspace group {}",
+                google_group.email
+            )
+        });
+    let group = find_or_create_group(
+        groups_service,
+        existing_groups,
+        &google_group.name,
+        &description,
+    )
+    .await?;
+
+    let identity_ids: Vec<String> = google_group
+        .members
+        .iter()
+        .filter(|member| member.r#type == "USER")
+        .filter(|member| member.status.as_deref().unwrap_or("ACTIVE") == "ACTIVE")
+        .filter_map(|member| member.email.as_deref())
+        .filter_map(|email| identities_by_email.get(&email.to_lowercase()))
+        .map(|identity| identity.id.clone())
+        .collect();
+
+    add_members_in_batches(groups_service, &group.id, &identity_ids).await?;
+
+    Ok((group, identity_ids.len()))
+}
@@ -0,0 +1,179 @@
+use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::common::command::ambassador_impl_Executable;
+use crate::common::database::models::AuditEntry;
+use crate::common::{command::Executable, error::BiError};
+
+use async_trait::async_trait;
+use chrono::DateTime;
+use clap::{Args, Subcommand};
+use serde_json::Value;
+use std::path::PathBuf;
+use tabled::settings::style::Style;
+use tabled::{Table, Tabled};
+
+// ====================================
+// History Commands
+// ====================================
+
+/// Browse the local journal of every mutating request the CLI sent to the Beyond Identity API.
+#[derive(Subcommand, ambassador::Delegate)]
+#[delegate(Executable)]
+pub enum HistoryCommands {
+    /// List recorded actions, most recent first.
+    List(List),
+
+    /// Display every recorded detail of an action, including the state before deletes and patches.
+    Show(Show),
+
+    /// Write the whole journal as JSON, e.g. for archiving or review.
+    Export(Export),
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|d| d.to_rfc3339())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+// An entry as shown to users, with recorded bodies expanded to JSON
+fn entry_to_json(entry: &AuditEntry) -> Result<Value, BiError> {
+    let mut value = serde_json::to_value(entry)?;
+    value["time"] = Value::String(format_timestamp(entry.timestamp));
+    for (field, body) in [
+        ("pre_image", &entry.pre_image),
+        ("request_body", &entry.request_body),
+    ] {
+        if let Some(body) = body {
+            value[field] =
+                serde_json::from_str(body).unwrap_or_else(|_| Value::String(body.clone()));
+        }
+    }
+    if let Some(undone_at) = entry.undone_at {
+        value["undone"] = Value::String(format_timestamp(undone_at));
+    }
+    Ok(value)
+}
+
+// ====================================
+// History List
+// ====================================
+
+#[derive(Args)]
+pub struct List {
+    /// Maximum number of actions to list
+    #[clap(long, default_value_t = 50)]
+    limit: i64,
+}
+
+#[derive(Tabled)]
+struct AuditEntryDisplay {
+    #[tabled(rename = "ID")]
+    id: i64,
+    #[tabled(rename = "Time")]
+    time: String,
+    #[tabled(rename = "User")]
+    os_user: String,
+    #[tabled(rename = "Method")]
+    method: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Resource ID")]
+    resource_id: String,
+    #[tabled(rename = "URL")]
+    url: String,
+}
+
+#[async_trait]
+impl Executable for List {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        let entries = api_client.db.get_audit_entries(Some(self.limit)).await?;
+        if entries.is_empty() {
+            println!("No recorded actions.");
+            return Ok(());
+        }
+
+        let display: Vec<AuditEntryDisplay> = entries
+            .into_iter()
+            .map(|e| AuditEntryDisplay {
+                id: e.id,
+                time: format_timestamp(e.timestamp),
+                os_user: e.os_user,
+                method: e.method,
+                status: e
+                    .status
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                resource_id: e.resource_id.unwrap_or_default(),
+                url: e.url,
+            })
+            .collect();
+
+        let mut table = Table::new(display);
+        table.with(Style::extended());
+        println!("{}", table);
+        Ok(())
+    }
+}
+
+// ====================================
+// History Show
+// ====================================
+
+#[derive(Args)]
+pub struct Show {
+    /// ID of the action, as shown by `history list`
+    id: i64,
+}
+
+#[async_trait]
+impl Executable for Show {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        let entry = api_client
+            .db
+            .get_audit_entry(self.id)
+            .await?
+            .ok_or_else(|| BiError::StringError(format!("No recorded action {}", self.id)))?;
+        println!("{}", serde_json::to_string_pretty(&entry_to_json(&entry)?)?);
+        Ok(())
+    }
+}
+
+// ====================================
+// History Export
+// ====================================
+
+#[derive(Args)]
+pub struct Export {
+    /// File to write to. Defaults to stdout.
+    #[clap(long)]
+    output: Option<PathBuf>,
+}
+
+#[async_trait]
+impl Executable for Export {
+    async fn execute(&self) -> Result<(), BiError> {
+        let api_client = ApiClient::new(None, None).await;
+        let entries = api_client
+            .db
+            .get_audit_entries(None)
+            .await?
+            .iter()
+            .rev()
+            .map(entry_to_json)
+            .collect::<Result<Vec<_>, _>>()?;
+        let json = serde_json::to_string_pretty(&entries)?;
+
+        match &self.output {
+            Some(path) => {
+                std::fs::write(path, json).map_err(|e| {
+                    BiError::StringError(format!("Failed to write {}: {}", path.display(), e))
+                })?;
+                println!("Exported {} action(s) to {}", entries.len(), path.display());
+            }
+            None => println!("{}", json),
+        }
+        Ok(())
+    }
+}
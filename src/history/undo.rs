@@ -0,0 +1,453 @@
+use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::beyond_identity::api::common::middleware::dry_run::DryRunMiddleware;
+use crate::beyond_identity::api::common::middleware::record_replay::REDACTED;
+use crate::common::command::Executable;
+use crate::common::database::models::AuditEntry;
+use crate::common::database::Database;
+use crate::common::error::BiError;
+
+use async_trait::async_trait;
+use clap::Args;
+use http::Method;
+use serde_json::{json, Map, Value};
+use url::Url;
+
+// ====================================
+// Undo
+// ====================================
+
+/// Reverse an action recorded in the history. Deleted identities and groups
+/// are recreated, group members removed with `delete-members` are added back,
+/// and patches are reverted to the values from before them, overwriting any
+/// later changes to the same fields. Anything that can't be reversed is listed
+/// after the undo.
+#[derive(Args)]
+pub struct Undo {
+    /// ID of the action, as shown by `history list`
+    id: i64,
+}
+
+#[async_trait]
+impl Executable for Undo {
+    async fn execute(&self) -> Result<(), BiError> {
+        let db = ApiClient::new(None, None).await.db;
+        let entry = db
+            .get_audit_entry(self.id)
+            .await?
+            .ok_or_else(|| BiError::StringError(format!("No recorded action {}", self.id)))?;
+
+        if entry.undone_at.is_some() {
+            return Err(BiError::StringError(format!(
+                "Action {} was already undone",
+                entry.id
+            )));
+        }
+        if !entry.status.is_some_and(|s| (200..300).contains(&s)) {
+            return Err(BiError::StringError(format!(
+                "Action {} did not succeed, so there is nothing to undo",
+                entry.id
+            )));
+        }
+
+        let action = Action::parse(&entry)?;
+        let api_client = api_client_for(&db, &action.tenant_id, action.realm_id.as_deref()).await?;
+        let notes = action.undo(&api_client, &entry).await?;
+
+        if !DryRunMiddleware::enabled() {
+            db.set_audit_entry_undone(entry.id).await?;
+        }
+        println!("Undid action {}.", entry.id);
+        if !notes.is_empty() {
+            println!("\nCan't be reversed:");
+            for note in notes {
+                println!("  - {}", note);
+            }
+        }
+        Ok(())
+    }
+}
+
+// A client for the tenant/realm the action was made against, which need not
+// be the default one.
+async fn api_client_for(
+    db: &Database,
+    tenant_id: &str,
+    realm_id: Option<&str>,
+) -> Result<ApiClient, BiError> {
+    let (tenant, realm) = db
+        .get_all_tenants_with_realms()
+        .await?
+        .into_iter()
+        .filter(|(tenant, _)| tenant.id == tenant_id)
+        .flat_map(|(tenant, realms)| realms.into_iter().map(move |r| (tenant.clone(), r)))
+        .find(|(_, realm)| realm_id.is_none_or(|id| realm.id == id))
+        .ok_or_else(|| {
+            BiError::StringError(format!(
+                "Tenant {} realm {} is no longer configured",
+                tenant_id,
+                realm_id.unwrap_or("-")
+            ))
+        })?;
+    Ok(ApiClient::new(Some(tenant), Some(realm)).await)
+}
+
+// A recorded request, broken down into the parts undo needs
+struct Action {
+    method: Method,
+    url: Url,
+    tenant_id: String,
+    realm_id: Option<String>,
+    // Path below the realm, or below the tenant for tenant-level requests
+    path: Vec<String>,
+    // Custom method suffix, e.g. `deleteMembers`
+    custom: Option<String>,
+}
+
+impl Action {
+    fn parse(entry: &AuditEntry) -> Result<Self, BiError> {
+        let method = Method::from_bytes(entry.method.as_bytes())
+            .map_err(|e| BiError::StringError(e.to_string()))?;
+        let url = Url::parse(&entry.url).map_err(BiError::InvalidUrl)?;
+
+        let mut segments: Vec<String> = url
+            .path_segments()
+            .map(|s| s.map(str::to_string).collect())
+            .unwrap_or_default();
+        let mut custom = None;
+        if let Some(last) = segments.last_mut() {
+            if let Some((segment, suffix)) = last.clone().split_once(':') {
+                *last = segment.to_string();
+                custom = Some(suffix.to_string());
+            }
+        }
+
+        let (tenant_id, realm_id, path) = match segments.as_slice() {
+            [v1, tenants, tenant_id, realms, realm_id, rest @ ..]
+                if v1 == "v1" && tenants == "tenants" && realms == "realms" =>
+            {
+                (tenant_id.clone(), Some(realm_id.clone()), rest.to_vec())
+            }
+            [v1, tenants, tenant_id, rest @ ..] if v1 == "v1" && tenants == "tenants" => {
+                (tenant_id.clone(), None, rest.to_vec())
+            }
+            _ => {
+                return Err(BiError::StringError(format!(
+                    "Action {} is not a Beyond Identity API request",
+                    entry.id
+                )))
+            }
+        };
+
+        Ok(Self {
+            method,
+            url,
+            tenant_id,
+            realm_id,
+            path,
+            custom,
+        })
+    }
+
+    // URL of a path below the realm of this action
+    fn realm_url(&self, path: &str) -> String {
+        let mut url = self.url.clone();
+        url.set_path(&format!(
+            "/v1/tenants/{}/realms/{}/{}",
+            self.tenant_id,
+            self.realm_id.as_deref().unwrap_or_default(),
+            path
+        ));
+        url.set_query(None);
+        url.to_string()
+    }
+
+    // URL of `path` below the tenant, or below the realm for realm-level actions
+    fn path_url(&self, path: &[String]) -> String {
+        let mut segments = vec!["v1", "tenants", self.tenant_id.as_str()];
+        if let Some(realm_id) = &self.realm_id {
+            segments.extend(["realms", realm_id.as_str()]);
+        }
+        segments.extend(path.iter().map(String::as_str));
+        let mut url = self.url.clone();
+        url.set_path(&segments.join("/"));
+        url.set_query(None);
+        url.to_string()
+    }
+
+    // Reverse the action and return the parts that could not be reversed
+    async fn undo(
+        &self,
+        api_client: &ApiClient,
+        entry: &AuditEntry,
+    ) -> Result<Vec<String>, BiError> {
+        let db = &api_client.db;
+        let path: Vec<&str> = self.path.iter().map(String::as_str).collect();
+
+        match (&self.method, path.as_slice(), self.custom.as_deref()) {
+            (&Method::DELETE, ["identities", _], None) => {
+                let previous = pre_image(entry)?;
+                let body = json!({
+                    "identity": {
+                        "display_name": previous["display_name"],
+                        "traits": previous["traits"],
+                    }
+                });
+                let new_id = self
+                    .recreate(api_client, "identities", "identity", &previous, &body)
+                    .await?;
+                if previous["status"] == "suspended" {
+                    api_client
+                        .send_request::<_, Value>(
+                            Method::PATCH,
+                            &self.realm_url(&format!("identities/{}", new_id)),
+                            Some(&json!({ "identity": { "id": new_id, "status": "suspended" } })),
+                        )
+                        .await?;
+                }
+                Ok(vec![
+                    format!(
+                        "The identity was assigned the new ID {}. Later commands must use it instead of {}.",
+                        new_id,
+                        id_of(&previous)
+                    ),
+                    "Group memberships, role assignments and credentials of the deleted identity are lost.".to_string(),
+                ])
+            }
+            (&Method::DELETE, ["groups", _], None) => {
+                let previous = pre_image(entry)?;
+                let body = json!({
+                    "group": {
+                        "display_name": previous["display_name"],
+                        "description": previous["description"],
+                    }
+                });
+                let new_id = self
+                    .recreate(api_client, "groups", "group", &previous, &body)
+                    .await?;
+                Ok(vec![
+                    format!(
+                        "The group was assigned the new ID {}. Later commands must use it instead of {}.",
+                        new_id,
+                        id_of(&previous)
+                    ),
+                    "Members and role assignments of the deleted group are lost.".to_string(),
+                ])
+            }
+            (&Method::POST, ["groups", group_id], Some(custom @ ("deleteMembers" | "addMembers"))) => {
+                let group_id = db.resolve_recreated_id(group_id).await?;
+                let mut identity_ids = Vec::new();
+                for id in request_body(entry)?["identity_ids"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                {
+                    identity_ids.push(db.resolve_recreated_id(id).await?);
+                }
+                let reverse = if custom == "deleteMembers" {
+                    "addMembers"
+                } else {
+                    "deleteMembers"
+                };
+                api_client
+                    .send_request::<_, Value>(
+                        Method::POST,
+                        &self.realm_url(&format!("groups/{}:{}", group_id, reverse)),
+                        Some(&json!({ "identity_ids": identity_ids })),
+                    )
+                    .await?;
+                println!(
+                    "{} {} identities {} group {}.",
+                    if reverse == "addMembers" { "Added" } else { "Removed" },
+                    identity_ids.len(),
+                    if reverse == "addMembers" { "to" } else { "from" },
+                    group_id
+                );
+                Ok(Vec::new())
+            }
+            (&Method::PATCH, _, None) => {
+                let previous = pre_image(entry)?;
+                let changes = request_body(entry)?;
+                let mut body = Map::new();
+                let mut redacted = Vec::new();
+                for (envelope, fields) in changes.as_object().into_iter().flatten() {
+                    let mut reverted = previous_values(&previous, fields);
+                    // Secrets were redacted before recording, so patching them
+                    // back would overwrite the real value with the placeholder
+                    remove_redacted(&mut reverted, envelope, &mut redacted);
+                    if let Some(id) = reverted.get("id").and_then(Value::as_str) {
+                        reverted["id"] = json!(db.resolve_recreated_id(id).await?);
+                    }
+                    body.insert(envelope.clone(), reverted);
+                }
+                let restorable = body.values().any(|fields| {
+                    fields
+                        .as_object()
+                        .is_none_or(|fields| fields.keys().any(|key| key != "id"))
+                });
+                if !restorable {
+                    return Err(BiError::StringError(format!(
+                        "Action {} only changed secrets, whose previous values were not recorded: {}",
+                        entry.id,
+                        redacted.join(", ")
+                    )));
+                }
+
+                // Rebuild the path segment by segment, so only the resource ID
+                // itself is replaced by the ID it was recreated with
+                let mut path = self.path.clone();
+                if let Some(id) = previous.get("id").and_then(Value::as_str) {
+                    let current = db.resolve_recreated_id(id).await?;
+                    for segment in path.iter_mut().filter(|segment| *segment == id) {
+                        *segment = current.clone();
+                    }
+                }
+                let url = self.path_url(&path);
+                api_client
+                    .send_request::<_, Value>(Method::PATCH, &url, Some(&body))
+                    .await?;
+                println!("Restored the previous values of {}.", url);
+                Ok(redacted
+                    .into_iter()
+                    .map(|field| {
+                        format!(
+                            "{} was recorded redacted, so its previous value was not restored.",
+                            field
+                        )
+                    })
+                    .collect())
+            }
+            (&Method::POST, [.., "credentials", _], Some("revoke")) => Err(BiError::StringError(
+                "Revoked credentials can't be reinstated. The identity has to bind a new credential, e.g. with `api credential-binding-jobs create`.".to_string(),
+            )),
+            (&Method::DELETE, [], None) => Err(BiError::StringError(
+                "Deleted realms can't be recreated along with their identities, groups and configuration.".to_string(),
+            )),
+            _ => Err(BiError::StringError(format!(
+                "{} {} can't be undone. Only deleted identities and groups, group member changes and patches can.",
+                entry.method, entry.url
+            ))),
+        }
+    }
+
+    // Create a deleted resource again and remember its new ID
+    async fn recreate(
+        &self,
+        api_client: &ApiClient,
+        collection: &str,
+        kind: &str,
+        previous: &Value,
+        body: &Value,
+    ) -> Result<String, BiError> {
+        let created: Value = api_client
+            .send_request(Method::POST, &self.realm_url(collection), Some(body))
+            .await?;
+        let new_id = id_of(&created).to_string();
+        // A dry run only pretends to recreate, with a placeholder ID
+        if !DryRunMiddleware::enabled() {
+            api_client
+                .db
+                .set_recreated_id(id_of(previous), &new_id)
+                .await?;
+        }
+        println!("Recreated {} {} as {}.", kind, id_of(previous), new_id);
+        Ok(new_id)
+    }
+}
+
+fn id_of(value: &Value) -> &str {
+    value["id"].as_str().unwrap_or_default()
+}
+
+fn pre_image(entry: &AuditEntry) -> Result<Value, BiError> {
+    let body = entry.pre_image.as_deref().ok_or_else(|| {
+        BiError::StringError(format!(
+            "The state before action {} was not recorded",
+            entry.id
+        ))
+    })?;
+    Ok(serde_json::from_str(body)?)
+}
+
+fn request_body(entry: &AuditEntry) -> Result<Value, BiError> {
+    let body = entry.request_body.as_deref().ok_or_else(|| {
+        BiError::StringError(format!(
+            "The request body of action {} was not recorded",
+            entry.id
+        ))
+    })?;
+    Ok(serde_json::from_str(body)?)
+}
+
+// Remove fields holding the redaction placeholder, at any depth, and collect
+// their dotted paths below `prefix`
+fn remove_redacted(value: &mut Value, prefix: &str, removed: &mut Vec<String>) {
+    if let Value::Object(fields) = value {
+        fields.retain(|key, field| {
+            let path = format!("{}.{}", prefix, key);
+            if field.as_str() == Some(REDACTED) {
+                removed.push(path);
+                false
+            } else {
+                remove_redacted(field, &path, removed);
+                true
+            }
+        });
+    }
+}
+
+// The values `previous` had for every field set in `changes`
+fn previous_values(previous: &Value, changes: &Value) -> Value {
+    match changes {
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| {
+                    let before = previous.get(key).unwrap_or(&Value::Null);
+                    let reverted = if value.is_object() {
+                        previous_values(before, value)
+                    } else {
+                        before.clone()
+                    };
+                    (key.clone(), reverted)
+                })
+                .collect(),
+        ),
+        _ => previous.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacted_values_are_not_reverted() {
+        let previous = json!({
+            "display_name": "Portal",
+            "client_secret": REDACTED,
+            "config": { "token": REDACTED, "url": "https://example.com" },
+        });
+        let changes = json!({
+            "display_name": "New portal",
+            "client_secret": "new-secret",
+            "config": { "token": "new-token", "url": "https://example.org" },
+        });
+
+        let mut reverted = previous_values(&previous, &changes);
+        let mut removed = Vec::new();
+        remove_redacted(&mut reverted, "application", &mut removed);
+
+        assert_eq!(
+            reverted,
+            json!({
+                "display_name": "Portal",
+                "config": { "url": "https://example.com" },
+            })
+        );
+        assert_eq!(
+            removed,
+            vec!["application.client_secret", "application.config.token"]
+        );
+    }
+}
@@ -1,22 +1,44 @@
 mod ai;
+mod auth;
 mod beyond_identity;
 mod common;
 mod config;
+mod dev;
+mod entra;
+mod google;
+mod history;
 mod okta;
 mod onelogin;
+mod scim;
 
 use ai::command::AiCommands;
 use async_trait::async_trait;
+use auth::command::AuthCommands;
 use beyond_identity::api::common::command::BeyondIdentityApiCommands;
+use beyond_identity::api::common::middleware::authorization::{self, DEFAULT_REFRESH_MARGIN_SECS};
+use beyond_identity::api::common::middleware::dry_run::DryRunMiddleware;
+use beyond_identity::api::common::middleware::record_replay::{
+    RecordReplayMiddleware, RecordReplayMode,
+};
+use beyond_identity::api::common::middleware::retry::{RetryMiddleware, RetryPolicy};
 use beyond_identity::helper::command::BeyondIdentityHelperCommands;
 use clap::{Args, Parser, Subcommand};
 use clap_markdown::MarkdownOptions;
 use common::command::{ambassador_impl_Executable, Executable};
+use common::database::Database;
 use common::error::BiError;
 use config::command::ConfigCommands;
+use dev::command::DevCommands;
+use entra::command::EntraCommands;
+use google::command::GoogleCommands;
+use history::command::HistoryCommands;
+use history::undo::Undo;
 use log::LevelFilter;
 use okta::command::OktaCommands;
 use onelogin::command::OneloginCommands;
+use scim::command::ScimCommands;
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[clap(
@@ -30,6 +52,27 @@ pub struct Cli {
     command: Commands,
     #[clap(short, long)]
     log_level: Option<String>,
+    /// Profile to use instead of the active one
+    #[clap(long, global = true, env = "BI_PROFILE")]
+    profile: Option<String>,
+    /// Maximum number of times a request failing with a 5xx status or a network error is retried
+    #[clap(long, global = true, env = "BI_MAX_RETRIES", default_value_t = RetryPolicy::default().max_retries)]
+    max_retries: u32,
+    /// Maximum number of seconds a single request may spend waiting between retries
+    #[clap(long, global = true, env = "BI_RETRY_BUDGET", default_value_t = RetryPolicy::default().budget.as_secs())]
+    retry_budget: u64,
+    /// Number of seconds before expiry a cached access token is refreshed
+    #[clap(long, global = true, env = "BI_TOKEN_REFRESH_MARGIN_SECS", value_name = "SECS", default_value_t = DEFAULT_REFRESH_MARGIN_SECS)]
+    token_refresh_margin: u64,
+    /// Record every Beyond Identity API exchange to this directory, with secrets redacted
+    #[clap(long, global = true, value_name = "DIR", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+    /// Serve Beyond Identity API responses from the recordings in this directory instead of the network
+    #[clap(long, global = true, value_name = "DIR")]
+    replay: Option<PathBuf>,
+    /// Print every request that would change something in Beyond Identity instead of sending it
+    #[clap(long, global = true)]
+    dry_run: bool,
 }
 
 #[derive(Subcommand, ambassador::Delegate)]
@@ -39,10 +82,21 @@ enum Commands {
     #[clap(subcommand)]
     Config(ConfigCommands),
 
+    /// Inspect and manage cached access tokens
+    #[clap(subcommand)]
+    Auth(AuthCommands),
+
     /// Interact with Beyond Identity API endpoints
     #[clap(subcommand)]
     Api(BeyondIdentityApiCommands),
 
+    /// Browse the local journal of mutating API requests
+    #[clap(subcommand)]
+    History(HistoryCommands),
+
+    /// Reverse an action recorded in the history
+    Undo(Undo),
+
     /// Access helper functions for Beyond Identity API operations
     #[clap(subcommand)]
     Helper(BeyondIdentityHelperCommands),
@@ -59,6 +113,22 @@ enum Commands {
     #[clap(subcommand)]
     Onelogin(OneloginCommands),
 
+    /// Commands solely for fast migration off of Microsoft Entra ID
+    #[clap(subcommand)]
+    Entra(EntraCommands),
+
+    /// Commands solely for migration off of Google Workspace
+    #[clap(subcommand)]
+    Google(GoogleCommands),
+
+    /// Commands for migrating off of any SCIM 2.0 compliant identity provider
+    #[clap(subcommand)]
+    Scim(ScimCommands),
+
+    /// Tools for developing and testing the CLI locally
+    #[clap(subcommand)]
+    Dev(DevCommands),
+
     /// Generate Markdown documentation (hidden)
     #[clap(hide = true)]
     GenerateMarkdown(GenerateMarkdownCommand),
@@ -103,6 +173,28 @@ async fn main() {
     };
     env_logger::Builder::new().filter(None, log_level).init();
 
+    if let Some(profile) = &cli.profile {
+        Database::set_profile_override(profile);
+    }
+    // Load shared HTTP client settings before any client is built. Errors
+    // opening the database surface again once the command runs.
+    if let Ok(db) = Database::initialize().await {
+        if let Ok(Some(http_config)) = db.get_http_config().await {
+            common::http::configure(http_config);
+        }
+    }
+    if let Some(dir) = &cli.record {
+        RecordReplayMiddleware::configure(RecordReplayMode::Record(dir.clone()));
+    } else if let Some(dir) = &cli.replay {
+        RecordReplayMiddleware::configure(RecordReplayMode::Replay(dir.clone()));
+    }
+    DryRunMiddleware::configure(cli.dry_run);
+    RetryMiddleware::configure(RetryPolicy {
+        max_retries: cli.max_retries,
+        budget: Duration::from_secs(cli.retry_budget),
+    });
+    authorization::configure_refresh_margin(cli.token_refresh_margin);
+
     match cli.command.execute().await {
         Ok(_) => (),
         Err(e) => eprintln!("{}", e.to_string()),
@@ -2,13 +2,17 @@ use super::fast_migrate;
 
 use crate::beyond_identity::api::common::api_client::ApiClient;
 use crate::beyond_identity::api::common::middleware::rate_limit::RespectRateLimitMiddleware;
+use crate::beyond_identity::api::common::service::IdentitiesService;
+use crate::beyond_identity::api::identities::api::IdentitiesApi;
+use crate::beyond_identity::helper::sso_configs;
 use crate::common::command::ambassador_impl_Executable;
+use crate::common::http;
 use crate::common::{command::Executable, error::BiError};
 
 use async_trait::async_trait;
 use clap::{Args, Subcommand};
-use reqwest::Client;
 use reqwest_middleware::ClientBuilder;
+use std::path::PathBuf;
 
 // ====================================
 // Okta Commands
@@ -28,12 +32,20 @@ pub enum OktaCommands {
 // ====================================
 
 #[derive(Args)]
-pub struct FastMigrate;
+pub struct FastMigrate {
+    /// Report what would be migrated without creating SSO configs or assigning identities
+    #[clap(long)]
+    dry_run: bool,
+
+    /// Write the dry run report as JSON to this file instead of printing it
+    #[clap(long, requires = "dry_run")]
+    report: Option<PathBuf>,
+}
 
 #[async_trait]
 impl Executable for FastMigrate {
     async fn execute(&self) -> Result<(), BiError> {
-        let http_client = Client::new();
+        let http_client = http::client();
         let okta_client = ClientBuilder::new(http_client.clone())
             .with(RespectRateLimitMiddleware)
             .build();
@@ -46,6 +58,28 @@ impl Executable for FastMigrate {
             .expect("Failed to fetch okta applications");
 
         let selected_applications = fast_migrate::select_applications(&okta_applications);
+        if self.dry_run {
+            let beyond_identity_identities = IdentitiesService::new()
+                .build()
+                .await
+                .list_identities(None, None)
+                .await?
+                .identities;
+            let plans = selected_applications
+                .iter()
+                .filter_map(|app| {
+                    match fast_migrate::plan_sso_config(app, &beyond_identity_identities) {
+                        Ok(plan) => Some(plan),
+                        Err(err) => {
+                            println!("Skipping {}: {}", app.label, err);
+                            None
+                        }
+                    }
+                })
+                .collect::<Vec<_>>();
+            return sso_configs::print_sso_config_plans(&plans, self.report.as_deref());
+        }
+
         for app in selected_applications {
             match fast_migrate::create_sso_config_and_assign_identities(&api_client, &app).await {
                 Ok(sso_config) => println!(
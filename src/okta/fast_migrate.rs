@@ -305,10 +305,7 @@ fn filter_identities(
         .collect()
 }
 
-pub async fn create_sso_config_and_assign_identities(
-    api_client: &ApiClient,
-    okta_application: &OktaApplication,
-) -> Result<sso_configs::SsoConfigBookmark, BiError> {
+fn login_link_and_logo(okta_application: &OktaApplication) -> Result<(String, String), BiError> {
     let login_link = okta_application
         ._links
         .app_links
@@ -324,11 +321,45 @@ pub async fn create_sso_config_and_assign_identities(
             href: "https://static.byndid.com/logos/beyondidentity.png".to_string(),
             r#type: "image/png".to_string(),
         });
+    Ok((login_link.href.clone(), logo.href))
+}
+
+// Computes what `create_sso_config_and_assign_identities` would do without writing anything.
+pub fn plan_sso_config(
+    okta_application: &OktaApplication,
+    beyond_identity_identities: &[Identity],
+) -> Result<sso_configs::SsoConfigPlan, BiError> {
+    let (login_link, logo) = login_link_and_logo(okta_application)?;
+    let okta_users = okta_application
+        .embedded
+        .as_ref()
+        .map(|embedded| embedded.users.as_slice())
+        .unwrap_or_default();
+    let source_user_emails: Vec<Option<&str>> = okta_users
+        .iter()
+        .map(|user| user.profile.email.as_deref())
+        .collect();
+    let filtered_identities = filter_identities(okta_users, beyond_identity_identities);
+
+    Ok(sso_configs::plan_sso_config(
+        &okta_application.label,
+        login_link,
+        Some(logo),
+        &source_user_emails,
+        &filtered_identities,
+    ))
+}
+
+pub async fn create_sso_config_and_assign_identities(
+    api_client: &ApiClient,
+    okta_application: &OktaApplication,
+) -> Result<sso_configs::SsoConfigBookmark, BiError> {
+    let (login_link, logo) = login_link_and_logo(okta_application)?;
     let sso_config = sso_configs::create_sso_config(
         &api_client,
         okta_application.label.clone(),
-        login_link.href.clone(),
-        Some(logo.href),
+        login_link,
+        Some(logo),
     )
     .await?;
 
@@ -1,7 +1,13 @@
 use super::fast_migrate;
+use super::migrate_roles;
 
 use crate::beyond_identity::api::common::api_client::ApiClient;
+use crate::beyond_identity::api::common::service::{GroupsService, IdentitiesService};
+use crate::beyond_identity::api::groups::api::GroupsApi;
+use crate::beyond_identity::api::identities::api::IdentitiesApi;
+use crate::beyond_identity::helper::sso_configs;
 use crate::common::command::ambassador_impl_Executable;
+use crate::common::http;
 use crate::{
     beyond_identity::api::common::middleware::rate_limit::RespectRateLimitMiddleware,
     common::{command::Executable, error::BiError},
@@ -9,8 +15,8 @@ use crate::{
 
 use async_trait::async_trait;
 use clap::{Args, Subcommand};
-use reqwest::Client;
 use reqwest_middleware::ClientBuilder;
+use std::path::PathBuf;
 
 // ====================================
 // Onelogin Commands
@@ -23,6 +29,10 @@ pub enum OneloginCommands {
     /// Automatically migrate all OneLogin applications to Beyond Identity SSO and assign users based on existing OneLogin assignments.
     /// Each application tile in Beyond Identity will act as an opaque redirect to Onelogin.
     FastMigrate(FastMigrate),
+
+    /// Migrate OneLogin roles to Beyond Identity groups and add each role's users as group members.
+    /// Users are matched to existing Beyond Identity identities by email address.
+    MigrateRoles(MigrateRoles),
 }
 
 // ====================================
@@ -30,12 +40,20 @@ pub enum OneloginCommands {
 // ====================================
 
 #[derive(Args)]
-pub struct FastMigrate;
+pub struct FastMigrate {
+    /// Report what would be migrated without creating SSO configs or assigning identities
+    #[clap(long)]
+    dry_run: bool,
+
+    /// Write the dry run report as JSON to this file instead of printing it
+    #[clap(long, requires = "dry_run")]
+    report: Option<PathBuf>,
+}
 
 #[async_trait]
 impl Executable for FastMigrate {
     async fn execute(&self) -> Result<(), BiError> {
-        let http_client = Client::new();
+        let http_client = http::client();
         let onelogin_client = ClientBuilder::new(http_client.clone())
             .with(RespectRateLimitMiddleware)
             .build();
@@ -48,6 +66,20 @@ impl Executable for FastMigrate {
                 .expect("Failed to fetch onelogin applications");
 
         let selected_applications = fast_migrate::select_applications(&onelogin_applications);
+        if self.dry_run {
+            let beyond_identity_identities = IdentitiesService::new()
+                .build()
+                .await
+                .list_identities(None, None)
+                .await?
+                .identities;
+            let plans = selected_applications
+                .iter()
+                .map(|app| fast_migrate::plan_sso_config(app, &beyond_identity_identities))
+                .collect::<Vec<_>>();
+            return sso_configs::print_sso_config_plans(&plans, self.report.as_deref());
+        }
+
         for app in selected_applications {
             match fast_migrate::create_sso_config_and_assign_identities(&api_client, &app).await {
                 Ok(sso_config) => println!(
@@ -64,3 +96,59 @@ impl Executable for FastMigrate {
         Ok(())
     }
 }
+
+// ====================================
+// Onelogin MigrateRoles
+// ====================================
+
+#[derive(Args)]
+pub struct MigrateRoles;
+
+#[async_trait]
+impl Executable for MigrateRoles {
+    async fn execute(&self) -> Result<(), BiError> {
+        let http_client = http::client();
+        let onelogin_client = ClientBuilder::new(http_client.clone())
+            .with(RespectRateLimitMiddleware)
+            .build();
+        let api_client = ApiClient::new(None, None).await;
+        let onelogin_config = api_client.db.get_onelogin_config().await?.expect("Failed to load Onelogin Configuration. Make sure to setup Onelogin before running this command.");
+
+        let onelogin_roles =
+            migrate_roles::fetch_onelogin_roles(&onelogin_client, &onelogin_config)
+                .await
+                .expect("Failed to fetch onelogin roles");
+
+        let selected_roles = migrate_roles::select_roles(&onelogin_roles);
+
+        let groups_service = GroupsService::new().build().await;
+        let existing_groups = groups_service.list_groups(None, None).await?.groups;
+        let beyond_identity_identities = IdentitiesService::new()
+            .build()
+            .await
+            .list_identities(None, None)
+            .await?
+            .identities;
+
+        for role in selected_roles {
+            match migrate_roles::create_group_and_assign_identities(
+                &groups_service,
+                &existing_groups,
+                &beyond_identity_identities,
+                &role,
+            )
+            .await
+            {
+                Ok((group, member_count)) => println!(
+                    "Group {} ({}) migrated from role {} with {} members",
+                    group.display_name, group.id, role.name, member_count
+                ),
+                Err(err) => {
+                    println!("Failed to migrate role {}: {}", role.name, err)
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
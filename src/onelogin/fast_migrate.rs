@@ -6,7 +6,7 @@ use crate::common::database::models::OneloginConfig;
 use crate::common::error::BiError;
 
 use reqwest_middleware::ClientWithMiddleware as Client;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
 use std::io::{self, Write};
 
@@ -29,11 +29,14 @@ pub struct OneLoginApplication {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OneLoginUser {
     id: u64,
-    email: Option<String>,
+    pub email: Option<String>,
     username: Option<String>,
 }
 
-async fn get_onelogin_access_token(
+// OneLogin caps v2 list endpoints at 1000 results per page.
+const PAGE_LIMIT: u32 = 1000;
+
+pub async fn get_onelogin_access_token(
     client: &Client,
     onelogin_config: &OneloginConfig,
 ) -> Result<String, BiError> {
@@ -83,36 +86,17 @@ pub async fn fetch_onelogin_applications(
 
     let access_token = get_onelogin_access_token(client, onelogin_config).await?;
 
-    let response = client
-        .get(&url)
-        .header("Authorization", format!("Bearer: {}", access_token))
-        .send()
-        .await?;
-
-    let status = response.status();
-    let response_text = response.text().await?;
-
-    log::debug!(
-        "{} response status: {} and text: {}",
-        url,
-        status,
-        response_text
-    );
-
-    if !status.is_success() {
-        return Err(BiError::RequestError(status, response_text));
-    }
-
-    let mut applications: Vec<OneLoginApplication> = serde_json::from_str(&response_text)?;
+    let mut applications: Vec<OneLoginApplication> =
+        fetch_all_pages(client, &url, &access_token).await?;
 
     for app in &mut applications {
         log::info!("Fetching assigned users for app: {:?}", app.name);
-        let users = get_users_assigned_to_app(client, onelogin_config, app.id).await?;
+        let users =
+            get_users_assigned_to_app(client, onelogin_config, app.id, &access_token).await?;
         app.assigned_users = users;
         // Fetch application directly to get more info like "icon_url"
         let application =
-            fetch_onelogin_application(client, onelogin_config, app.id, access_token.clone())
-                .await?;
+            fetch_onelogin_application(client, onelogin_config, app.id, &access_token).await?;
         app.icon = application.icon;
         app.login_link = format!("{}/launch/{}", onelogin_config.domain, app.id);
     }
@@ -120,11 +104,70 @@ pub async fn fetch_onelogin_applications(
     Ok(applications)
 }
 
+// Fetches every page of a OneLogin v2 list endpoint by following the
+// `After-Cursor` response header until no further cursor is returned.
+pub async fn fetch_all_pages<T: DeserializeOwned>(
+    client: &Client,
+    url: &str,
+    access_token: &str,
+) -> Result<Vec<T>, BiError> {
+    let mut results = Vec::new();
+    let mut cursor: Option<String> = None;
+
+    loop {
+        let mut request = client
+            .get(url)
+            .header("Authorization", format!("Bearer: {}", access_token))
+            .query(&[("limit", PAGE_LIMIT)]);
+
+        if let Some(ref cursor) = cursor {
+            request = request.query(&[("cursor", cursor)]);
+        }
+
+        let response = request.send().await?;
+
+        let status = response.status();
+        let next_cursor = extract_after_cursor(&response);
+        let response_text = response.text().await?;
+
+        log::debug!(
+            "{} response status: {} and text: {}",
+            url,
+            status,
+            response_text
+        );
+
+        if !status.is_success() {
+            return Err(BiError::RequestError(status, response_text));
+        }
+
+        let page: Vec<T> = serde_json::from_str(&response_text)?;
+        let page_is_empty = page.is_empty();
+        results.extend(page);
+
+        match next_cursor {
+            Some(next) if !page_is_empty => cursor = Some(next),
+            _ => break,
+        }
+    }
+
+    Ok(results)
+}
+
+fn extract_after_cursor(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get("after-cursor")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
 async fn fetch_onelogin_application(
     client: &Client,
     onelogin_config: &OneloginConfig,
     app_id: u64,
-    access_token: String,
+    access_token: &str,
 ) -> Result<OneLoginApplication, BiError> {
     let url = format!("{}/api/2/apps/{}", onelogin_config.domain, app_id);
 
@@ -157,32 +200,11 @@ async fn get_users_assigned_to_app(
     client: &Client,
     onelogin_config: &OneloginConfig,
     app_id: u64,
+    access_token: &str,
 ) -> Result<Vec<OneLoginUser>, BiError> {
     let url = format!("{}/api/2/apps/{}/users", onelogin_config.domain, app_id);
 
-    let access_token = get_onelogin_access_token(client, onelogin_config).await?;
-
-    let response = client
-        .get(&url)
-        .header("Authorization", format!("Bearer: {}", access_token))
-        .send()
-        .await?;
-
-    let status = response.status();
-    let response_text = response.text().await?;
-
-    log::debug!(
-        "{} response status: {} and text: {}",
-        url,
-        status,
-        response_text
-    );
-
-    if !status.is_success() {
-        return Err(BiError::RequestError(status, response_text));
-    }
-
-    let assigned_users: Vec<OneLoginUser> = serde_json::from_str(&response_text)?;
+    let assigned_users: Vec<OneLoginUser> = fetch_all_pages(client, &url, access_token).await?;
 
     println!(
         "Fetched {} users for app id {}",
@@ -225,7 +247,7 @@ pub fn select_applications(applications: &[OneLoginApplication]) -> Vec<OneLogin
         .collect()
 }
 
-fn filter_identities(
+pub fn filter_identities(
     onelogin_users: &[OneLoginUser],
     beyond_identity_identities: &[Identity],
 ) -> Vec<Identity> {
@@ -246,6 +268,30 @@ fn filter_identities(
         .collect()
 }
 
+// Computes what `create_sso_config_and_assign_identities` would do without writing anything.
+pub fn plan_sso_config(
+    onelogin_application: &OneLoginApplication,
+    beyond_identity_identities: &[Identity],
+) -> sso_configs::SsoConfigPlan {
+    let source_user_emails: Vec<Option<&str>> = onelogin_application
+        .assigned_users
+        .iter()
+        .map(|user| user.email.as_deref())
+        .collect();
+    let filtered_identities = filter_identities(
+        &onelogin_application.assigned_users,
+        beyond_identity_identities,
+    );
+
+    sso_configs::plan_sso_config(
+        &onelogin_application.name,
+        onelogin_application.login_link.clone(),
+        onelogin_application.icon.clone(),
+        &source_user_emails,
+        &filtered_identities,
+    )
+}
+
 pub async fn create_sso_config_and_assign_identities(
     api_client: &ApiClient,
     onelogin_application: &OneLoginApplication,
@@ -0,0 +1,128 @@
+use super::fast_migrate::{
+    fetch_all_pages, filter_identities, get_onelogin_access_token, OneLoginUser,
+};
+
+use crate::beyond_identity::api::common::service::GroupsService;
+use crate::beyond_identity::api::groups::types::Group;
+use crate::beyond_identity::api::identities::types::Identity;
+use crate::beyond_identity::helper::groups::{add_members_in_batches, find_or_create_group};
+use crate::common::database::models::OneloginConfig;
+use crate::common::error::BiError;
+
+use reqwest_middleware::ClientWithMiddleware as Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OneLoginRole {
+    id: u64,
+    pub name: String,
+    #[serde(default, skip_deserializing)]
+    members: Vec<OneLoginUser>,
+}
+
+pub async fn fetch_onelogin_roles(
+    client: &Client,
+    onelogin_config: &OneloginConfig,
+) -> Result<Vec<OneLoginRole>, BiError> {
+    let url = format!("{}/api/2/roles", onelogin_config.domain);
+
+    let access_token = get_onelogin_access_token(client, onelogin_config).await?;
+
+    let mut roles: Vec<OneLoginRole> = fetch_all_pages(client, &url, &access_token).await?;
+
+    for role in &mut roles {
+        log::info!("Fetching users for role: {:?}", role.name);
+        let url = format!("{}/api/2/roles/{}/users", onelogin_config.domain, role.id);
+        role.members = fetch_all_pages(client, &url, &access_token).await?;
+        println!(
+            "Fetched {} users for role id {}",
+            role.members.len(),
+            role.id
+        );
+    }
+
+    Ok(roles)
+}
+
+pub fn select_roles(roles: &[OneLoginRole]) -> Vec<OneLoginRole> {
+    println!("Select roles to migrate (comma separated indices or 'all' for all roles):");
+
+    for (index, role) in roles.iter().enumerate() {
+        println!(
+            "{}: {} - {} ({} users)",
+            index,
+            role.name,
+            role.id,
+            role.members.len()
+        );
+    }
+
+    print!("Your selection: ");
+    io::stdout().flush().unwrap();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+    let input = input.trim();
+
+    if input == "all" {
+        return roles.to_vec();
+    }
+
+    let indices: Vec<usize> = input
+        .split(',')
+        .map(|s| s.trim().parse().unwrap())
+        .collect();
+
+    indices.into_iter().map(|i| roles[i].clone()).collect()
+}
+
+// Creates a Beyond Identity group for the role (or reuses an existing group with
+// the same display name) and adds every identity whose primary email matches a
+// member of the OneLogin role.
+pub async fn create_group_and_assign_identities(
+    groups_service: &GroupsService,
+    existing_groups: &[Group],
+    beyond_identity_identities: &[Identity],
+    onelogin_role: &OneLoginRole,
+) -> Result<(Group, usize), BiError> {
+    let group = find_or_create_group(
+        groups_service,
+        existing_groups,
+        &onelogin_role.name,
+        &format!("Migrated from OneLogin role {}", onelogin_role.id),
+    )
+    .await?;
+
+    let filtered_identities = filter_identities(&onelogin_role.members, beyond_identity_identities);
+    let identity_ids: Vec<String> = filtered_identities
+        .iter()
+        .map(|identity| identity.id.clone())
+        .collect();
+
+    add_members_in_batches(groups_service, &group.id, &identity_ids).await?;
+
+    let matched_emails: HashSet<&str> = filtered_identities
+        .iter()
+        .filter_map(|identity| identity.traits.primary_email_address.as_deref())
+        .collect();
+    let unmatched = onelogin_role
+        .members
+        .iter()
+        .filter(|user| {
+            !user
+                .email
+                .as_deref()
+                .is_some_and(|email| matched_emails.contains(email))
+        })
+        .count();
+    if unmatched > 0 {
+        println!(
+            "{} users in role {} have no matching Beyond Identity identity",
+            unmatched, onelogin_role.name
+        );
+    }
+
+    Ok((group, identity_ids.len()))
+}
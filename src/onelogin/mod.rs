@@ -1,2 +1,3 @@
 pub mod command;
 pub mod fast_migrate;
+pub mod migrate_roles;
@@ -0,0 +1,107 @@
+use super::import;
+
+use crate::beyond_identity::api::common::filter::Filter;
+use crate::beyond_identity::api::common::middleware::rate_limit::RespectRateLimitMiddleware;
+use crate::beyond_identity::api::common::service::{GroupsService, IdentitiesService};
+use crate::beyond_identity::api::groups::api::GroupsApi;
+use crate::beyond_identity::api::identities::api::IdentitiesApi;
+use crate::common::command::ambassador_impl_Executable;
+use crate::common::{command::Executable, error::BiError};
+
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+
+// ====================================
+// SCIM Commands
+// ====================================
+
+/// Commands for migrating from any SCIM 2.0 compliant identity provider to Beyond Identity.
+#[derive(Subcommand, ambassador::Delegate)]
+#[delegate(Executable)]
+pub enum ScimCommands {
+    /// Import users and groups from a SCIM 2.0 server. Users are created as Beyond Identity identities
+    /// (existing identities are matched by username or email) and groups are recreated with the same memberships.
+    Import(Import),
+}
+
+// ====================================
+// SCIM Import
+// ====================================
+
+#[derive(Args)]
+pub struct Import {
+    /// Base URL of the SCIM 2.0 server, e.g. https://example.com/scim/v2
+    #[clap(long)]
+    base_url: String,
+
+    /// Bearer token used to authenticate with the SCIM server
+    #[clap(long)]
+    token: String,
+
+    /// SCIM filter passed through to the source server when listing users. Filters follow the SCIM grammar from RFC-7644 Section 3.4.2.2.
+    ///
+    /// Example:
+    ///
+    ///   --source-filter "userName sw \"j\" and active eq true"
+    #[clap(long)]
+    source_filter: Option<String>,
+
+    /// Only import users, without recreating groups
+    #[clap(long)]
+    skip_groups: bool,
+}
+
+#[async_trait]
+impl Executable for Import {
+    async fn execute(&self) -> Result<(), BiError> {
+        let scim_client = RespectRateLimitMiddleware::new_client();
+        let base_url = self.base_url.trim_end_matches('/');
+        let source_filter = Filter::new(self.source_filter.clone())?;
+
+        let scim_users =
+            import::fetch_scim_users(&scim_client, base_url, &self.token, source_filter.as_ref())
+                .await?;
+
+        let identities_service = IdentitiesService::new().build().await;
+        let beyond_identity_identities = identities_service
+            .list_identities(None, None)
+            .await?
+            .identities;
+        let identities_by_scim_id =
+            import::import_users(&identities_service, &scim_users, beyond_identity_identities)
+                .await?;
+
+        if self.skip_groups {
+            return Ok(());
+        }
+
+        let scim_groups = import::fetch_scim_groups(&scim_client, base_url, &self.token).await?;
+
+        let groups_service = GroupsService::new().build().await;
+        let existing_groups = groups_service.list_groups(None, None).await?.groups;
+
+        for scim_group in scim_groups {
+            match import::import_group(
+                &groups_service,
+                &existing_groups,
+                &identities_by_scim_id,
+                &scim_group,
+            )
+            .await
+            {
+                Ok((group, member_count)) => println!(
+                    "Group {} ({}) imported with {} members",
+                    group.display_name, group.id, member_count
+                ),
+                Err(err) => {
+                    println!(
+                        "Failed to import group {}: {}",
+                        scim_group.display_name, err
+                    )
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
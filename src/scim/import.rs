@@ -0,0 +1,289 @@
+use crate::beyond_identity::api::common::filter::Filter;
+use crate::beyond_identity::api::common::service::{GroupsService, IdentitiesService};
+use crate::beyond_identity::api::groups::types::Group;
+use crate::beyond_identity::api::identities::types::{
+    CreateIdentity, CreateIdentityRequest, Identity, Traits, Type,
+};
+use crate::beyond_identity::helper::groups::{add_members_in_batches, find_or_create_group};
+use crate::beyond_identity::helper::identities;
+use crate::common::error::BiError;
+
+use reqwest_middleware::ClientWithMiddleware as Client;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Number of resources requested per page. Servers may return fewer.
+const PAGE_SIZE: usize = 100;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListResponse<T> {
+    total_results: usize,
+    #[serde(rename = "Resources", default = "Vec::new")]
+    resources: Vec<T>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUser {
+    pub id: String,
+    pub user_name: String,
+    #[serde(default)]
+    external_id: Option<String>,
+    #[serde(default)]
+    display_name: Option<String>,
+    #[serde(default)]
+    name: Option<ScimName>,
+    #[serde(default)]
+    emails: Vec<ScimEmail>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimName {
+    given_name: Option<String>,
+    family_name: Option<String>,
+    formatted: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ScimEmail {
+    value: String,
+    #[serde(default)]
+    primary: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimGroup {
+    id: String,
+    pub display_name: String,
+    #[serde(default)]
+    members: Option<Vec<ScimMember>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ScimMember {
+    value: String,
+    #[serde(default)]
+    r#type: Option<String>,
+}
+
+impl ScimUser {
+    // The primary email, falling back to the first listed email when none is marked primary.
+    fn primary_email(&self) -> Option<&str> {
+        self.emails
+            .iter()
+            .find(|email| email.primary)
+            .or(self.emails.first())
+            .map(|email| email.value.as_str())
+    }
+
+    fn to_create_identity_request(&self) -> CreateIdentityRequest {
+        let name = self.name.as_ref();
+        CreateIdentityRequest {
+            identity: CreateIdentity {
+                display_name: self
+                    .display_name
+                    .clone()
+                    .or_else(|| name.and_then(|name| name.formatted.clone()))
+                    .unwrap_or_else(|| self.user_name.clone()),
+                traits: Traits {
+                    r#type: Type::TraitsV0,
+                    username: self.user_name.clone(),
+                    primary_email_address: self.primary_email().map(str::to_string),
+                    external_id: self.external_id.clone(),
+                    family_name: name.and_then(|name| name.family_name.clone()),
+                    given_name: name.and_then(|name| name.given_name.clone()),
+                },
+            },
+        }
+    }
+}
+
+// Fetches every resource from a SCIM list endpoint using startIndex/count paging
+// (RFC 7644 Section 3.4.2.4).
+async fn fetch_all_pages<T: DeserializeOwned>(
+    client: &Client,
+    url: &str,
+    token: &str,
+    filter: Option<&Filter>,
+) -> Result<Vec<T>, BiError> {
+    let mut results = Vec::new();
+    // SCIM indices are 1-based.
+    let mut start_index = 1;
+
+    loop {
+        let mut request = client
+            .get(url)
+            .bearer_auth(token)
+            .header("Accept", "application/scim+json, application/json")
+            .query(&[("startIndex", start_index), ("count", PAGE_SIZE)]);
+
+        if let Some(filter) = filter {
+            request = request.query(&[("filter", &filter.0)]);
+        }
+
+        let response = request.send().await?;
+
+        let status = response.status();
+        let response_text = response.text().await?;
+
+        log::debug!(
+            "{} response status: {} and text: {}",
+            url,
+            status,
+            response_text
+        );
+
+        if !status.is_success() {
+            return Err(BiError::RequestError(status, response_text));
+        }
+
+        let page: ListResponse<T> = serde_json::from_str(&response_text)?;
+        let page_len = page.resources.len();
+        results.extend(page.resources);
+        start_index += page_len;
+
+        if page_len == 0 || results.len() >= page.total_results {
+            break;
+        }
+    }
+
+    Ok(results)
+}
+
+pub async fn fetch_scim_users(
+    client: &Client,
+    base_url: &str,
+    token: &str,
+    filter: Option<&Filter>,
+) -> Result<Vec<ScimUser>, BiError> {
+    let url = format!("{}/Users", base_url);
+    let users: Vec<ScimUser> = fetch_all_pages(client, &url, token, filter).await?;
+    println!("Fetched {} SCIM users", users.len());
+    Ok(users)
+}
+
+pub async fn fetch_scim_groups(
+    client: &Client,
+    base_url: &str,
+    token: &str,
+) -> Result<Vec<ScimGroup>, BiError> {
+    let url = format!("{}/Groups", base_url);
+    let mut groups: Vec<ScimGroup> = fetch_all_pages(client, &url, token, None).await?;
+
+    // Some servers leave members out of list responses, so fetch those groups individually.
+    for group in groups.iter_mut().filter(|group| group.members.is_none()) {
+        let url = format!("{}/Groups/{}", base_url, group.id);
+        let response = client
+            .get(&url)
+            .bearer_auth(token)
+            .header("Accept", "application/scim+json, application/json")
+            .send()
+            .await?;
+
+        let status = response.status();
+        let response_text = response.text().await?;
+
+        log::debug!(
+            "{} response status: {} and text: {}",
+            url,
+            status,
+            response_text
+        );
+
+        if !status.is_success() {
+            return Err(BiError::RequestError(status, response_text));
+        }
+
+        let full_group: ScimGroup = serde_json::from_str(&response_text)?;
+        group.members = full_group.members;
+    }
+
+    println!("Fetched {} SCIM groups", groups.len());
+    Ok(groups)
+}
+
+// Creates identities for SCIM users that do not exist yet and returns a map of SCIM
+// user ID to the matching Beyond Identity identity.
+pub async fn import_users(
+    identities_service: &IdentitiesService,
+    scim_users: &[ScimUser],
+    beyond_identity_identities: Vec<Identity>,
+) -> Result<HashMap<String, Identity>, BiError> {
+    let requests: Vec<CreateIdentityRequest> = scim_users
+        .iter()
+        .map(ScimUser::to_create_identity_request)
+        .collect();
+
+    let identities = identities::create_missing_identities(
+        identities_service,
+        &requests,
+        beyond_identity_identities,
+    )
+    .await?;
+
+    let by_username: HashMap<String, &Identity> = identities
+        .iter()
+        .map(|identity| (identity.traits.username.to_lowercase(), identity))
+        .collect();
+    let by_email: HashMap<String, &Identity> = identities
+        .iter()
+        .filter_map(|identity| {
+            identity
+                .traits
+                .primary_email_address
+                .as_deref()
+                .map(|email| (email.to_lowercase(), identity))
+        })
+        .collect();
+
+    Ok(scim_users
+        .iter()
+        .filter_map(|user| {
+            by_username
+                .get(&user.user_name.to_lowercase())
+                .or_else(|| {
+                    user.primary_email()
+                        .and_then(|email| by_email.get(&email.to_lowercase()))
+                })
+                .map(|identity| (user.id.clone(), (*identity).clone()))
+        })
+        .collect())
+}
+
+// Recreates a SCIM group as a Beyond Identity group and adds every imported user
+// member. Nested group members are not expanded.
+pub async fn import_group(
+    groups_service: &GroupsService,
+    existing_groups: &[Group],
+    identities_by_scim_id: &HashMap<String, Identity>,
+    scim_group: &ScimGroup,
+) -> Result<(Group, usize), BiError> {
+    let group = find_or_create_group(
+        groups_service,
+        existing_groups,
+        &scim_group.display_name,
+        &format!("Imported from SCIM group {}", scim_group.id),
+    )
+    .await?;
+
+    let identity_ids: Vec<String> = scim_group
+        .members
+        .iter()
+        .flatten()
+        .filter(|member| {
+            member
+                .r#type
+                .as_deref()
+                .is_none_or(|t| t.eq_ignore_ascii_case("user"))
+        })
+        .filter_map(|member| identities_by_scim_id.get(&member.value))
+        .map(|identity| identity.id.clone())
+        .collect();
+
+    add_members_in_batches(groups_service, &group.id, &identity_ids).await?;
+
+    Ok((group, identity_ids.len()))
+}
@@ -0,0 +1,388 @@
+// Runs the `bi` binary against `bi dev mock-server` on an ephemeral port, with
+// its data directory in a scratch directory so the user's tenants are untouched.
+
+use serde_json::Value;
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+struct MockServer {
+    child: Child,
+    home: PathBuf,
+    port: u16,
+}
+
+impl MockServer {
+    fn start(name: &str) -> MockServer {
+        MockServer::start_with(name, &[])
+    }
+
+    // Starts the mock server with extra `bi dev mock-server` options
+    fn start_with(name: &str, options: &[&str]) -> MockServer {
+        let home = std::env::temp_dir().join(format!("bi-test-{}-{}", name, std::process::id()));
+        _ = std::fs::remove_dir_all(&home);
+        std::fs::create_dir_all(&home).unwrap();
+
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        // Serving the token endpoint on the API port lets the tenant's auth
+        // base URL be the same as its API base URL
+        let child = Command::new(env!("CARGO_BIN_EXE_bi"))
+            .args(["dev", "mock-server", "--port", &port.to_string()])
+            .args(["--auth-port", &port.to_string()])
+            .args(options)
+            .envs(isolated_env(&home))
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while TcpStream::connect(("127.0.0.1", port)).is_err() {
+            assert!(Instant::now() < deadline, "mock server did not start");
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        MockServer { child, home, port }
+    }
+
+    // Runs `bi` with `args`, asserting that it succeeds, and returns its stdout
+    fn bi(&self, args: &[&str]) -> String {
+        let output = Command::new(env!("CARGO_BIN_EXE_bi"))
+            .args(args)
+            .envs(isolated_env(&self.home))
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "bi {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    fn bi_json(&self, args: &[&str]) -> Value {
+        serde_json::from_str(&self.bi(args)).unwrap()
+    }
+
+    fn add_tenant(&self) {
+        let base_url = format!("http://localhost:{}", self.port);
+        let output = self.bi(&[
+            "config",
+            "tenants",
+            "add",
+            "--tenant-id",
+            "tenant-1",
+            "--realm-id",
+            "realm-1",
+            "--application-id",
+            "application-1",
+            "--client-id",
+            "client-1",
+            "--client-secret",
+            "secret-1",
+            "--api-base-url",
+            &base_url,
+            "--auth-base-url",
+            &base_url,
+        ]);
+        assert!(output.contains("Provisioned tenant tenant-1 realm realm-1"));
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        _ = self.child.kill();
+        _ = self.child.wait();
+        _ = std::fs::remove_dir_all(&self.home);
+    }
+}
+
+// Keeps the data directory and secrets of `bi` inside `home`, so tests never
+// touch the developer's tenants or OS keyring
+fn isolated_env(home: &std::path::Path) -> Vec<(&'static str, PathBuf)> {
+    vec![
+        ("HOME", home.to_path_buf()),
+        ("XDG_DATA_HOME", home.join("data")),
+        ("XDG_CONFIG_HOME", home.join("config")),
+        ("BI_SECRETS_BACKEND", PathBuf::from("database")),
+    ]
+}
+
+fn ids(list: &Value, field: &str) -> Vec<String> {
+    let mut ids: Vec<String> = list[field]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["id"].as_str().unwrap().to_string())
+        .collect();
+    ids.sort();
+    ids
+}
+
+fn create_identity(server: &MockServer, username: &str) -> String {
+    let email = format!("{}@example.com", username);
+    let identity = server.bi_json(&[
+        "api",
+        "identities",
+        "create",
+        "--display-name",
+        username,
+        "--type",
+        "traits_v0",
+        "--username",
+        username,
+        "--primary-email-address",
+        &email,
+    ]);
+    assert_eq!(identity["traits"]["username"], username);
+    assert_eq!(identity["tenant_id"], "tenant-1");
+    assert_eq!(identity["realm_id"], "realm-1");
+    identity["id"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn identities_are_created_and_listed() {
+    let server = MockServer::start("identities");
+    server.add_tenant();
+
+    let jane = create_identity(&server, "jane");
+    let john = create_identity(&server, "john");
+
+    let identities = server.bi_json(&["api", "identities", "list"]);
+    let mut expected = vec![jane.clone(), john];
+    expected.sort();
+    assert_eq!(ids(&identities, "identities"), expected);
+
+    let identity = server.bi_json(&["api", "identities", "get", "--id", &jane]);
+    assert_eq!(
+        identity["traits"]["primary_email_address"],
+        "jane@example.com"
+    );
+}
+
+#[test]
+fn group_members_are_added_and_listed() {
+    let server = MockServer::start("groups");
+    server.add_tenant();
+
+    let jane = create_identity(&server, "jane");
+    let group = server.bi_json(&[
+        "api",
+        "groups",
+        "create",
+        "--display-name",
+        "Engineering",
+        "--description",
+        "Engineers",
+    ]);
+    assert_eq!(group["display_name"], "Engineering");
+    let group_id = group["id"].as_str().unwrap();
+
+    server.bi(&[
+        "api",
+        "groups",
+        "add-members",
+        "--id",
+        group_id,
+        "--identity-ids",
+        &jane,
+    ]);
+
+    let members = server.bi_json(&["api", "groups", "list-members", "--id", group_id]);
+    assert_eq!(ids(&members, "identities"), vec![jane]);
+}
+
+#[test]
+fn listing_follows_every_page() {
+    let server = MockServer::start_with("pages", &["--max-page-size", "2"]);
+    server.add_tenant();
+
+    let mut expected: Vec<String> = ["ann", "bob", "cat", "dan", "eve"]
+        .iter()
+        .map(|username| create_identity(&server, username))
+        .collect();
+    expected.sort();
+
+    let identities = server.bi_json(&["api", "identities", "list"]);
+    assert_eq!(ids(&identities, "identities"), expected);
+
+    let identities = server.bi_json(&["api", "identities", "list", "--limit", "3"]);
+    assert_eq!(ids(&identities, "identities").len(), 3);
+}
+
+#[test]
+fn throttled_requests_are_retried_after_the_delay() {
+    let server =
+        MockServer::start_with("throttle", &["--throttle-every", "2", "--retry-after", "1"]);
+    server.add_tenant();
+
+    // Every second API request is answered with 429 and Retry-After: 1
+    let started = Instant::now();
+    let jane = create_identity(&server, "jane");
+    let john = create_identity(&server, "john");
+    let identities = server.bi_json(&["api", "identities", "list"]);
+    assert!(started.elapsed() >= Duration::from_secs(1));
+
+    let mut expected = vec![jane, john];
+    expected.sort();
+    assert_eq!(ids(&identities, "identities"), expected);
+}
+
+#[test]
+fn offboarded_identity_is_reinstated() {
+    let server = MockServer::start("offboard");
+    server.add_tenant();
+
+    let jane = create_identity(&server, "jane");
+    let group = server.bi_json(&[
+        "api",
+        "groups",
+        "create",
+        "--display-name",
+        "Engineering",
+        "--description",
+        "Engineers",
+    ]);
+    let group_id = group["id"].as_str().unwrap();
+    server.bi(&[
+        "api",
+        "groups",
+        "add-members",
+        "--id",
+        group_id,
+        "--identity-ids",
+        &jane,
+    ]);
+
+    let output = server.bi(&["helper", "offboard", "jane@example.com"]);
+    assert!(output.contains("Removed from groups: Engineering"));
+    // A second offboarding would leave nothing to restore
+    let output = server.bi(&["helper", "offboard", "jane@example.com"]);
+    assert!(output.contains("already offboarded"));
+    let identity = server.bi_json(&["api", "identities", "get", "--id", &jane]);
+    assert_eq!(identity["status"], "suspended");
+    let members = server.bi_json(&["api", "groups", "list-members", "--id", group_id]);
+    assert!(ids(&members, "identities").is_empty());
+
+    server.bi(&["helper", "reinstate", "jane@example.com"]);
+    let identity = server.bi_json(&["api", "identities", "get", "--id", &jane]);
+    assert_eq!(identity["status"], "active");
+    let members = server.bi_json(&["api", "groups", "list-members", "--id", group_id]);
+    assert_eq!(ids(&members, "identities"), vec![jane]);
+}
+
+#[test]
+fn duplicates_are_found() {
+    let server = MockServer::start("duplicates");
+    server.add_tenant();
+
+    let jane = create_identity(&server, "jane");
+    let duplicate = server.bi_json(&[
+        "api",
+        "identities",
+        "create",
+        "--display-name",
+        "Jane Doe",
+        "--type",
+        "traits_v0",
+        "--username",
+        "jdoe",
+        "--primary-email-address",
+        "JANE@example.com",
+    ]);
+    create_identity(&server, "john");
+
+    let clusters = server.bi_json(&["helper", "find-duplicates", "--format", "json"]);
+    let clusters = clusters.as_array().unwrap();
+    assert_eq!(clusters.len(), 1);
+    assert_eq!(
+        clusters[0]["matched_on"],
+        serde_json::json!(["email:jane@example.com"])
+    );
+    let mut expected = vec![jane, duplicate["id"].as_str().unwrap().to_string()];
+    expected.sort();
+    assert_eq!(ids(&clusters[0], "identities"), expected);
+}
+
+#[test]
+fn purge_deletes_only_identities_still_suspended() {
+    let server = MockServer::start("purge");
+    server.add_tenant();
+
+    let jane = create_identity(&server, "jane");
+    let john = create_identity(&server, "john");
+    for email in ["jane@example.com", "john@example.com"] {
+        server.bi(&["helper", "offboard", email, "--delete-after-days", "0"]);
+    }
+    // Reactivated outside of `reinstate`
+    server.bi(&[
+        "api",
+        "identities",
+        "patch",
+        "--id",
+        &john,
+        "--type",
+        "traits_v0",
+        "--status",
+        "active",
+    ]);
+
+    let output = server.bi(&["helper", "purge-offboarded", "--yes"]);
+    assert!(output.contains("1 offboarded identities will be deleted"));
+    assert!(output.contains(&format!("Deleted identity {}", jane)));
+    assert!(output.contains(&format!("Skipping identity {}", john)));
+
+    let identities = server.bi_json(&["api", "identities", "list"]);
+    assert_eq!(ids(&identities, "identities"), vec![john]);
+}
+
+#[test]
+fn revocation_continues_past_failed_lookups() {
+    let server = MockServer::start("revoke");
+    server.add_tenant();
+
+    let jane = create_identity(&server, "jane");
+    server.bi(&[
+        "api",
+        "credential-binding-jobs",
+        "create",
+        "--identity-id",
+        &jane,
+        "--delivery-method",
+        "return",
+        "--authenticator-config-id",
+        "config-1",
+    ]);
+
+    let targets = server.home.join("targets.csv");
+    let results = server.home.join("results.csv");
+    std::fs::write(
+        &targets,
+        format!("identity_id,email\n{},jane\nmissing,ghost\n,blank\n", jane),
+    )
+    .unwrap();
+    let output = server.bi(&[
+        "helper",
+        "revoke-credentials",
+        "--file",
+        targets.to_str().unwrap(),
+        "--results",
+        results.to_str().unwrap(),
+        "--yes",
+    ]);
+    assert!(output.contains("Skipped 1 rows"));
+    assert!(output.contains("Revoked 1 of 1 credentials."));
+
+    let results = std::fs::read_to_string(&results).unwrap();
+    let rows: Vec<&str> = results.lines().collect();
+    assert_eq!(rows.len(), 3);
+    assert!(rows.iter().any(|row| row.starts_with("missing,,failed,")));
+    assert!(rows
+        .iter()
+        .any(|row| row.starts_with(&jane) && row.contains(",revoked,")));
+}